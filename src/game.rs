@@ -1,32 +1,89 @@
+pub mod classes;
+pub mod combat;
+pub mod data;
+pub mod display;
+pub mod equipment;
+pub mod error;
+pub mod graphics;
+pub mod input;
+pub mod items;
+pub mod magic;
+pub mod persistence;
+pub mod progression;
+pub mod rules;
+pub mod stats;
 pub mod ui;
+pub mod vitals;
 
 use bevy::{
   app::AppExit,
-  input::mouse::{MouseMotion, MouseWheel},
+  ecs::system::SystemParam,
+  input::mouse::MouseMotion,
   prelude::*,
-  tasks::Task,
+  tasks::{AsyncComputeTaskPool, Task},
   window::CursorGrabMode,
 };
-
-use crate::{
-  fatal_error,
-  storage::{
-    saves::{Attributes as SavedAttributes, SaveData, SaveDataBuilder},
-    SystemInformation,
-  },
+use futures_lite::future;
+
+use self::{
+  combat::{CombatConfig, Hurtbox},
+  equipment::{BaseAttributes, Buffs, Equipment},
+  error::GameError,
+  input::ActionInput,
+  items::{Inventory, Items},
+  magic::Spellbook,
+  persistence::{AutosaveTimer, FinalSave},
+  progression::Progression,
+  stats::{StatCurves, Stats},
+  ui::error_modal::ErrorModal,
+  vitals::{Stamina, VitalsBundle, VitalsConfig},
+};
+use crate::storage::{
+  saves::{self, AttributeKind, Attributes as SavedAttributes, CameraMode, SaveData},
+  Action, SystemInformation,
 };
+use anyhow::Result;
+use std::time::Duration;
 
 const PLAYER_SIZE: f32 = 100.0;
-const DEADZONE: f32 = 0.15;
-const MOUSE_SENSITIVITY: f32 = 0.1;
 const UP: Vec3 = Vec3::Z;
 
 #[derive(Resource)]
 pub struct GameInfo {
+  camera_mode: CameraMode,
   compute_front: fn(Vec3) -> Vec3, // current front -> new front
   focus_camera: fn(Vec3, Vec3) -> (Vec3, Vec3), // player pos, direction -> cam pos, cam focus
 }
 
+impl GameInfo {
+  fn new(camera_mode: CameraMode) -> Self {
+    let mut info = Self {
+      camera_mode,
+      compute_front: player_first_person_front,
+      focus_camera: cam_first_person_target_fn,
+    };
+    info.set_camera_mode(camera_mode);
+    info
+  }
+
+  fn set_camera_mode(&mut self, camera_mode: CameraMode) {
+    self.camera_mode = camera_mode;
+    match camera_mode {
+      CameraMode::FirstPerson => {
+        self.compute_front = player_first_person_front;
+        self.focus_camera = cam_first_person_target_fn;
+      }
+      CameraMode::ThirdPerson => {
+        self.compute_front = player_third_person_front;
+        self.focus_camera = cam_third_person_target_fn;
+      }
+    }
+  }
+}
+
+#[derive(Resource)]
+pub struct PlayTime(Duration);
+
 #[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Hash, States)]
 pub enum GameState {
   #[default]
@@ -37,11 +94,26 @@ pub enum GameState {
   CharacterCreate,
   SettingsMenu,
   Gameplay,
+  ErrorModal,
 
   // debug
   UiPlayground,
 }
 
+// screens drawn over gameplay, player control is suspended while one is open
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Hash, States)]
+pub enum Overlay {
+  #[default]
+  None,
+  LevelUp,
+  Inventory,
+  Pause,
+}
+
+// everything spawned for a play session, despawned when the session ends
+#[derive(Component)]
+pub struct SessionEntity;
+
 pub fn startup(
   mut commands: Commands,
   mut next_state: ResMut<NextState<GameState>>,
@@ -52,66 +124,183 @@ pub fn startup(
 }
 
 pub fn global_input_handler(
-  kbd: Res<Input<KeyCode>>,
-  mut exit: EventWriter<AppExit>,
+  input: ActionInput,
+  state: Res<State<GameState>>,
   mut next_state: ResMut<NextState<GameState>>,
 ) {
-  if kbd.just_pressed(KeyCode::F9) {
+  // a session has to be quit properly first, and settings may be listening for a new binding
+  let allowed = !matches!(state.get(), GameState::Gameplay | GameState::SettingsMenu);
+  if allowed && input.just_pressed(Action::UiPlayground) {
     next_state.set(GameState::UiPlayground);
   }
+}
 
-  if kbd.just_pressed(KeyCode::Escape) {
-    exit.send(AppExit);
+// escape opens the pause menu, or backs out of whichever overlay is open
+pub fn pause_input(
+  input: ActionInput,
+  overlay: Res<State<Overlay>>,
+  mut next_overlay: ResMut<NextState<Overlay>>,
+) {
+  if !input.just_pressed(Action::Pause) {
+    return;
+  }
+
+  match overlay.get() {
+    Overlay::None => next_overlay.set(Overlay::Pause),
+    _ => next_overlay.set(Overlay::None),
+  }
+}
+
+#[derive(Event, Clone, Copy)]
+pub enum QuitEvent {
+  ToMainMenu,
+  ToDesktop,
+}
+
+// what a play session leaves in the world, cleared on the way back to the main menu
+#[derive(SystemParam)]
+pub struct SessionTeardown<'w, 's> {
+  commands: Commands<'w, 's>,
+  entities: Query<'w, 's, Entity, With<SessionEntity>>,
+  next_state: ResMut<'w, NextState<GameState>>,
+  next_overlay: ResMut<'w, NextState<Overlay>>,
+}
+
+impl SessionTeardown<'_, '_> {
+  // returns the camera the menus get in place of the session's
+  fn end(&mut self) -> Entity {
+    for entity in self.entities.iter() {
+      self.commands.entity(entity).despawn_recursive();
+    }
+    self.commands.remove_resource::<GameInfo>();
+    self.commands.remove_resource::<PlayTime>();
+    self.commands.remove_resource::<AutosaveTimer>();
+    self.next_overlay.set(Overlay::None);
+    self.next_state.set(GameState::MainMenu);
+    self.commands.spawn(Camera2dBundle::default()).id()
+  }
+}
+
+impl QuitEvent {
+  pub fn handler(
+    mut event_reader: EventReader<Self>,
+    mut exit: EventWriter<AppExit>,
+    mut sys_info: ResMut<SystemInformation>,
+    mut final_save: FinalSave,
+    mut teardown: SessionTeardown,
+  ) {
+    let Some(event) = event_reader.iter().last() else {
+      return;
+    };
+
+    match event {
+      QuitEvent::ToMainMenu => {
+        final_save.save(&sys_info);
+        // the session camera went with the world, menus need their own
+        sys_info.current_camera = Some(teardown.end());
+      }
+      // saved on the way out by persistence::save_on_exit
+      QuitEvent::ToDesktop => exit.send(AppExit),
+    }
   }
 }
 
 #[derive(Component)]
 pub struct LoadPlayer {
-  task: Task<SaveData>,
+  id: String,
+  task: Task<Result<SaveData>>,
+}
+
+impl LoadPlayer {
+  pub fn poll(
+    mut commands: Commands,
+    mut loads: Query<(Entity, &mut LoadPlayer)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    sys_info: Res<SystemInformation>,
+    mut event_writer: EventWriter<SaveDataLoadedEvent>,
+  ) {
+    for (entity, mut load) in loads.iter_mut() {
+      if let Some(result) = future::block_on(future::poll_once(&mut load.task)) {
+        match result {
+          Ok(save_data) => event_writer.send(SaveDataLoadedEvent {
+            id: load.id.clone(),
+            save_data,
+          }),
+          Err(e) => {
+            let error = GameError::from_load(load.id.clone(), e);
+            error!("{}", error);
+            let backup = saves::usable_backup(&sys_info.save_path(&load.id));
+            commands.insert_resource(ErrorModal::new(error, backup));
+            next_state.set(GameState::ErrorModal);
+          }
+        }
+        commands.entity(entity).despawn();
+      }
+    }
+  }
 }
 
 #[derive(Event)]
-pub struct StartGameEvent {
-  name: String,
+pub enum StartGameEvent {
+  // an existing character, by save id
+  Load(String),
+  // a freshly created character, written out before play begins
+  Create(Box<SaveData>),
 }
 
 impl StartGameEvent {
   pub fn handle(
+    mut commands: Commands,
     mut event: EventReader<Self>,
+    mut next_state: ResMut<NextState<GameState>>,
     sys_info: Res<SystemInformation>,
-    mut event_writer: EventWriter<SaveDataLoadedEvent>,
   ) {
     if let Some(event) = event.into_iter().next() {
-      let file_path = sys_info.game_saves_path.join(format!("{}.ms", event.name));
-      if file_path.exists() {
-        // load existing save
-
-        if let Ok(save_data) = std::fs::read(file_path) {
-          if let Ok(save_data) = bincode::deserialize::<SaveData>(&save_data) {
-            event_writer.send(SaveDataLoadedEvent(save_data));
-          } else {
-            fatal_error("player save data is corrupt");
+      let load = match event {
+        StartGameEvent::Load(id) => {
+          let file_path = sys_info.save_path(id);
+          LoadPlayer {
+            id: id.clone(),
+            task: AsyncComputeTaskPool::get()
+              .spawn(async move { SaveData::load_or_backup(&file_path) }),
           }
-        } else {
-          fatal_error("could not read save data file");
         }
-      } else {
-        // create new character
-        event_writer.send(SaveDataLoadedEvent(
-          SaveDataBuilder::new()
-            .name(event.name.clone())
-            .attributes(SavedAttributes::default())
-            .build(),
-        ));
-      }
+        StartGameEvent::Create(save_data) => {
+          let save_data = save_data.as_ref().clone();
+          let id = saves::generate_id(&sys_info.game_saves_path);
+          let file_path = sys_info.save_path(&id);
+          LoadPlayer {
+            id,
+            task: AsyncComputeTaskPool::get().spawn(async move {
+              save_data.save(&file_path)?;
+              Ok(save_data)
+            }),
+          }
+        }
+      };
+      commands.spawn(load);
     } else {
-      fatal_error("began game with no character")
+      let error = GameError::NoCharacter;
+      error!("{}", error);
+      commands.insert_resource(ErrorModal::new(error, None));
+      next_state.set(GameState::ErrorModal);
     }
   }
 }
 
+// loaded game data a session is built from
+#[derive(SystemParam)]
+pub struct SessionData<'w> {
+  curves: Res<'w, StatCurves>,
+  combat_config: Res<'w, CombatConfig>,
+  items: Res<'w, Items>,
+}
+
 #[derive(Event)]
-pub struct SaveDataLoadedEvent(SaveData);
+pub struct SaveDataLoadedEvent {
+  id: String,
+  save_data: SaveData,
+}
 
 impl SaveDataLoadedEvent {
   pub fn handle(
@@ -121,8 +310,14 @@ impl SaveDataLoadedEvent {
     mut next_state: ResMut<NextState<GameState>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    data: SessionData,
   ) {
-    for event in event_reader.iter() {
+    let SessionData {
+      curves,
+      combat_config,
+      items,
+    } = data;
+    if let Some(event) = event_reader.iter().next() {
       let save_data = event.data();
       if let Some(entity) = sys_info.current_camera {
         commands.entity(entity).despawn();
@@ -137,52 +332,101 @@ impl SaveDataLoadedEvent {
             },
             Front::default(),
             EulerAngles {
-              yaw: 90.0,
-              pitch: 0.0,
-              roll: 0.0,
+              yaw: save_data.camera.yaw,
+              pitch: save_data.camera.pitch,
             },
+            SessionEntity,
           ))
           .id(),
       );
+      let player_transform = save_data
+        .player
+        .map(|player| Transform {
+          translation: Vec3::from_array(player.translation),
+          rotation: Quat::from_array(player.rotation),
+          ..default()
+        })
+        .unwrap_or_else(|| Transform::from_translation(Vec3::new(0.0, 0.0, PLAYER_SIZE / 2.0)));
+      let equipment = Equipment::from(save_data.equipment.clone());
+      let attributes = equipment::effective(&save_data.attributes, equipment.modifiers(&items));
+      let stats = Stats::derive(&attributes, &curves);
+      let vitals = VitalsBundle::new(&stats, save_data.vitals);
+      let progression = Progression::from(save_data.progression);
       commands.spawn((
         PlayerCharacter,
+        SessionEntity,
+        SaveId(event.id.clone()),
         Name(save_data.name.clone()),
-        Attributes::from(save_data.attributes.clone()),
+        Class(save_data.class.clone()),
+        (
+          BaseAttributes(save_data.attributes.clone()),
+          equipment,
+          Buffs::default(),
+        ),
+        attributes,
+        stats,
+        vitals,
+        progression,
+        Spellbook::from(save_data.spells.clone()),
+        Inventory::new(save_data.inventory.clone(), &items),
+        Hurtbox::cube(PLAYER_SIZE),
         PbrBundle {
           mesh: meshes.add(shape::Cube::new(PLAYER_SIZE).into()),
           material: materials.add(Color::PURPLE.into()),
-          transform: Transform::from_translation(Vec3::new(0.0, 0.0, PLAYER_SIZE / 2.0)),
+          transform: player_transform,
           ..default()
         },
       ));
-      commands.spawn(PbrBundle {
-        mesh: meshes.add(shape::Plane::from_size(PLAYER_SIZE * 5.0).into()),
-        material: materials.add(Color::RED.into()),
-        transform: Transform::from_rotation(Quat::from_axis_angle(Vec3::X, 90.0_f32.to_radians())),
-        ..default()
-      });
-      commands.spawn(PbrBundle {
-        mesh: meshes.add(shape::Plane::from_size(PLAYER_SIZE * 5.0).into()),
-        material: materials.add(Color::RED.into()),
-        ..default()
-      });
+      commands.spawn((
+        PbrBundle {
+          mesh: meshes.add(shape::Plane::from_size(PLAYER_SIZE * 5.0).into()),
+          material: materials.add(Color::RED.into()),
+          transform: Transform::from_rotation(Quat::from_axis_angle(
+            Vec3::X,
+            90.0_f32.to_radians(),
+          )),
+          ..default()
+        },
+        SessionEntity,
+      ));
+      commands.spawn((
+        PbrBundle {
+          mesh: meshes.add(shape::Plane::from_size(PLAYER_SIZE * 5.0).into()),
+          material: materials.add(Color::RED.into()),
+          ..default()
+        },
+        SessionEntity,
+      ));
+      combat::spawn_training_dummies(&mut commands, &mut meshes, &mut materials, &combat_config);
+      items::spawn_world_items(&mut commands, &mut meshes, &mut materials, &items);
+      commands.insert_resource(GameInfo::new(save_data.camera.mode));
+      commands.insert_resource(PlayTime(save_data.play_time));
+      commands.insert_resource(AutosaveTimer::new(&sys_info.settings.saves));
       next_state.set(GameState::Gameplay);
-      break;
     }
   }
 
   fn data(&self) -> &SaveData {
-    &self.0
+    &self.save_data
   }
 }
 
 #[derive(Component)]
 pub struct PlayerCharacter;
 
+// the file the character is saved to
+#[derive(Component)]
+pub struct SaveId(String);
+
 #[derive(Component)]
 pub struct Name(String);
 
+// id of the character's class preset
 #[derive(Component)]
+pub struct Class(Option<String>);
+
+// effective attributes, see equipment::apply_modifiers
+#[derive(Component, PartialEq)]
 pub struct Attributes {
   // health
   vitality: u32,
@@ -214,8 +458,17 @@ impl Default for Front {
 }
 
 impl Attributes {
-  fn move_speed(&self) -> f32 {
-    self.agility as f32
+  fn get(&self, kind: AttributeKind) -> u32 {
+    match kind {
+      AttributeKind::Vitality => self.vitality,
+      AttributeKind::Endurance => self.endurance,
+      AttributeKind::Strength => self.strength,
+      AttributeKind::Dexterity => self.dexterity,
+      AttributeKind::Agility => self.agility,
+      AttributeKind::Intelligence => self.intelligence,
+      AttributeKind::Wisdom => self.wisdom,
+      AttributeKind::Mind => self.mind,
+    }
   }
 }
 
@@ -234,18 +487,39 @@ impl From<SavedAttributes> for Attributes {
   }
 }
 
-pub fn on_enter(mut commands: Commands, mut windows: Query<&mut Window>) {
-  let mut window = windows.single_mut();
-  window.cursor.grab_mode = CursorGrabMode::Locked;
-  window.cursor.visible = false;
-  commands.insert_resource(GameInfo {
-    compute_front: player_first_person_front,
-    focus_camera: cam_first_person_target_fn,
-  });
+fn lock_cursor(window: &mut Window, locked: bool) {
+  window.cursor.grab_mode = if locked {
+    CursorGrabMode::Locked
+  } else {
+    CursorGrabMode::None
+  };
+  window.cursor.visible = !locked;
+}
+
+// also entered on the way back from settings, with the pause menu still open
+pub fn on_enter(overlay: Res<State<Overlay>>, mut windows: Query<&mut Window>) {
+  lock_cursor(&mut windows.single_mut(), *overlay.get() == Overlay::None);
 }
 
-pub fn on_exit(mut commands: Commands) {
-  commands.remove_resource::<GameInfo>();
+// the session outlives a visit to settings, see QuitEvent for tearing it down
+pub fn on_exit(mut windows: Query<&mut Window>) {
+  lock_cursor(&mut windows.single_mut(), false);
+}
+
+// for overlays that need the pointer
+pub fn release_cursor(mut windows: Query<&mut Window>) {
+  lock_cursor(&mut windows.single_mut(), false);
+}
+
+// takes the pointer back once an overlay closes, unless gameplay closed with it
+pub fn restore_cursor(state: Res<State<GameState>>, mut windows: Query<&mut Window>) {
+  if *state.get() == GameState::Gameplay {
+    lock_cursor(&mut windows.single_mut(), true);
+  }
+}
+
+pub fn track_play_time(time: Res<Time>, mut play_time: ResMut<PlayTime>) {
+  play_time.0 += time.delta();
 }
 
 pub fn player_first_person_front(front: Vec3) -> Vec3 {
@@ -256,89 +530,65 @@ pub fn player_third_person_front(front: Vec3) -> Vec3 {
   Vec3::new(front.x, front.y, 0.0).normalize()
 }
 
+type MovingPlayer<'w, 's> = Query<
+  'w,
+  's,
+  (&'static mut Transform, &'static Stats, &'static mut Stamina),
+  With<PlayerCharacter>,
+>;
+type CameraFront<'w, 's> = Query<'w, 's, &'static Front, With<Camera3d>>;
+
 pub fn player_movement_system(
-  keyboard_input: Res<Input<KeyCode>>,
-  gamepads: Res<Gamepads>,
-  gamepad_buttons: Res<Input<GamepadButton>>,
-  gamepad_axis: Res<Axis<GamepadAxis>>,
+  input: ActionInput,
   time: Res<Time>,
+  vitals_config: Res<VitalsConfig>,
   mut game_info: ResMut<GameInfo>,
-  mut query: ParamSet<(
-    Query<(&mut Transform, &Attributes), With<PlayerCharacter>>,
-    Query<&Front, With<Camera3d>>,
-  )>,
+  mut query: ParamSet<(MovingPlayer, CameraFront)>,
 ) {
   // actions
 
-  let should_swap_cam = keyboard_input.just_pressed(KeyCode::F3)
-    || gamepads
-      .iter()
-      .next()
-      .map(|gp| gamepad_buttons.just_pressed(GamepadButton::new(gp, GamepadButtonType::Select)))
-      .unwrap_or_default();
-  if should_swap_cam {
-    game_info.compute_front = if game_info.compute_front == player_first_person_front {
-      player_third_person_front
-    } else {
-      player_first_person_front
-    };
-
-    game_info.focus_camera = if game_info.focus_camera == cam_first_person_target_fn {
-      cam_third_person_target_fn
-    } else {
-      cam_first_person_target_fn
-    };
+  if input.just_pressed(Action::ToggleCamera) {
+    let camera_mode = game_info.camera_mode.toggled();
+    game_info.set_camera_mode(camera_mode);
   }
 
   let front = query.p1().single().direction;
   let front = (game_info.compute_front)(front);
   let mut player_query = query.p0();
-  let move_speed = player_query.single().1.move_speed();
-  let mut transform = player_query.single_mut().0;
+  let (mut transform, stats, mut stamina) = player_query.single_mut();
 
   let mut movement = Vec3::default();
 
   let mut moved = false;
-  if keyboard_input.pressed(KeyCode::W) {
+  if input.pressed(Action::MoveForward) {
     movement += front;
     moved = true;
-  } else if keyboard_input.pressed(KeyCode::S) {
+  } else if input.pressed(Action::MoveBack) {
     movement -= front;
     moved = true;
   }
 
-  if keyboard_input.pressed(KeyCode::A) {
+  if input.pressed(Action::MoveLeft) {
     movement -= front.cross(UP);
     moved = true;
-  } else if keyboard_input.pressed(KeyCode::D) {
+  } else if input.pressed(Action::MoveRight) {
     movement += front.cross(UP);
     moved = true;
   }
 
-  for gamepad in gamepads.iter() {
-    let (x, y) = (
-      gamepad_axis
-        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
-        .unwrap_or_default(),
-      gamepad_axis
-        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
-        .unwrap_or_default(),
-    );
-
-    if x.abs() > DEADZONE {
-      movement += front.cross(UP) * x;
-      moved = true;
-    }
-
-    if y.abs() > DEADZONE {
-      movement += front * y;
-      moved = true;
-    }
-
-    break;
+  let stick = input.move_stick();
+  if stick != Vec2::ZERO {
+    movement += front.cross(UP) * stick.x + front * stick.y;
+    moved = true;
   }
 
   if moved {
+    let mut move_speed = stats.move_speed;
+    if input.pressed(Action::Sprint) && !stamina.is_empty() {
+      stamina.drain(vitals_config.sprint_cost * time.delta_seconds());
+      move_speed *= vitals_config.sprint_multiplier;
+    }
+
     let movement = movement.normalize() * move_speed * time.delta().as_millis() as f32;
     transform.translation += movement;
   }
@@ -348,8 +598,6 @@ pub fn player_movement_system(
 pub struct EulerAngles {
   yaw: f32,
   pitch: f32,
-  #[allow(unused)]
-  roll: f32,
 }
 
 pub fn cam_first_person_target_fn(player_pos: Vec3, direction: Vec3) -> (Vec3, Vec3) {
@@ -360,55 +608,37 @@ pub fn cam_third_person_target_fn(player_pos: Vec3, direction: Vec3) -> (Vec3, V
   (player_pos - (direction * PLAYER_SIZE * 5.0), player_pos)
 }
 
+type FocusingCamera<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static mut Transform,
+    &'static mut Front,
+    &'static mut EulerAngles,
+  ),
+  With<Camera3d>,
+>;
+type PlayerTransform<'w, 's> = Query<'w, 's, &'static Transform, With<PlayerCharacter>>;
+
 pub fn focus_camera_system(
   mut mouse_motion: EventReader<MouseMotion>,
-  gamepads: Res<Gamepads>,
-  gamepad_input: Res<Axis<GamepadAxis>>,
+  input: ActionInput,
   game_info: Res<GameInfo>,
-  mut query: ParamSet<(
-    Query<(&mut Transform, &mut Front, &mut EulerAngles), With<Camera3d>>,
-    Query<&Transform, With<PlayerCharacter>>,
-  )>,
+  mut query: ParamSet<(FocusingCamera, PlayerTransform)>,
 ) {
   let player_pos = query.p1().single().translation;
 
   let mut cam_query = query.p0();
   let cam_query = cam_query.single_mut();
 
-  let (mouse_x, mouse_y) = mouse_motion
-    .iter()
-    .map(|motion| motion.delta)
-    .reduce(|c, n| c + n)
-    .map(|offsets| (offsets.x * MOUSE_SENSITIVITY, offsets.y * MOUSE_SENSITIVITY))
-    .unwrap_or_default();
-
-  let (gamepad_x, gamepad_y) = gamepads
-    .iter()
-    .next()
-    .map(|gp| {
-      (
-        gamepad_input
-          .get(GamepadAxis::new(gp, GamepadAxisType::RightStickX))
-          .unwrap_or_default(),
-        gamepad_input
-          .get(GamepadAxis::new(gp, GamepadAxisType::RightStickY))
-          .unwrap_or_default(),
-      )
-    })
-    .map(|(x, y)| {
-      (
-        if x.abs() > DEADZONE { x } else { 0.0 },
-        if y.abs() > DEADZONE { y } else { 0.0 },
-      )
-    })
-    .unwrap_or_default();
+  let look = input.look(mouse_motion.iter().map(|motion| motion.delta).sum());
 
   let (yaw_rad, pitch_rad) = {
     // set cam rotation
     let mut euler_angles = cam_query.2;
 
-    euler_angles.yaw -= mouse_x + gamepad_x;
-    euler_angles.pitch -= mouse_y - gamepad_y;
+    euler_angles.yaw -= look.x;
+    euler_angles.pitch -= look.y;
 
     euler_angles.yaw %= 360.0;
 
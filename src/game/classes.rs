@@ -0,0 +1,124 @@
+use super::{data, equipment::Equipment, items::Items, rules};
+use crate::storage::saves::{Attributes, ItemStack};
+use anyhow::{anyhow, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ClassPreset {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  pub attributes: Attributes,
+  #[serde(default)]
+  pub starting_items: Vec<String>,
+  #[serde(default)]
+  pub starting_spells: Vec<String>,
+}
+
+impl ClassPreset {
+  // starting items, worn where they fit and carried otherwise
+  pub fn starting_kit(&self, items: &Items) -> (Equipment, Vec<ItemStack>) {
+    let mut equipment = Equipment::default();
+    let mut inventory = Vec::new();
+    for id in &self.starting_items {
+      let worn = items.get(id).is_some_and(|item| {
+        equipment
+          .free_slot(item)
+          .is_some_and(|slot| equipment.equip(slot, item, &self.attributes).is_ok())
+      });
+      if !worn {
+        inventory.push(ItemStack {
+          id: id.clone(),
+          count: 1,
+        });
+      }
+    }
+    (equipment, inventory)
+  }
+}
+
+#[derive(Resource, Deserialize)]
+pub struct ClassPresets {
+  #[serde(rename = "class")]
+  classes: Vec<ClassPreset>,
+}
+
+impl ClassPresets {
+  const FILE: &'static str = "classes.toml";
+
+  pub fn load() -> Result<Self> {
+    Self::validated(data::load(Self::FILE)?)
+  }
+
+  // a class the player couldn't have built by hand is a data error, not a bonus
+  fn validated(presets: Self) -> Result<Self> {
+    for class in &presets.classes {
+      rules::validate(&class.attributes).map_err(|e| {
+        anyhow!(
+          "class {} in {} has an invalid build: {}",
+          class.id,
+          Self::FILE,
+          e
+        )
+      })?;
+    }
+    Ok(presets)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &ClassPreset> {
+    self.classes.iter()
+  }
+
+  pub fn get(&self, id: &str) -> Option<&ClassPreset> {
+    self.classes.iter().find(|class| class.id == id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(attributes: &str) -> Result<ClassPresets> {
+    let data = format!(
+      "[[class]]\nid = \"test\"\nname = \"Test\"\ndescription = \"\"\n\n[class.attributes]\n{}",
+      attributes
+    );
+    ClassPresets::validated(toml::from_str(&data)?)
+  }
+
+  #[test]
+  fn shipped_classes_are_valid() {
+    assert!(ClassPresets::load().is_ok());
+  }
+
+  #[test]
+  fn accepts_a_full_build() {
+    let presets = parse(
+      "vitality = 5\nendurance = 5\nstrength = 6\ndexterity = 3\n\
+       agility = 2\nintelligence = 1\nwisdom = 1\nmind = 1",
+    )
+    .unwrap();
+    assert!(presets.get("test").is_some());
+  }
+
+  #[test]
+  fn rejects_unspent_points() {
+    let err = parse(
+      "vitality = 1\nendurance = 1\nstrength = 1\ndexterity = 1\n\
+       agility = 1\nintelligence = 1\nwisdom = 1\nmind = 1",
+    )
+    .err()
+    .unwrap();
+    assert!(err.to_string().contains("class test"));
+  }
+
+  #[test]
+  fn rejects_attributes_out_of_range() {
+    assert!(parse(
+      "vitality = 9\nendurance = 5\nstrength = 5\ndexterity = 1\n\
+       agility = 1\nintelligence = 1\nwisdom = 1\nmind = 1",
+    )
+    .is_err());
+  }
+}
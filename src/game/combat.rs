@@ -0,0 +1,306 @@
+use super::{
+  data,
+  input::ActionInput,
+  magic::Ward,
+  progression::ExperienceEvent,
+  stats::Stats,
+  vitals::{Health, Pool, Stamina},
+  Front, PlayerCharacter, SessionEntity, PLAYER_SIZE,
+};
+use crate::storage::Action;
+use anyhow::Result;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Resource, Deserialize)]
+pub struct CombatConfig {
+  pub windup: f32,
+  pub active: f32,
+  pub recovery: f32,
+  pub stamina_cost: f32,
+  pub reach: f32,
+  pub hitbox_size: f32,
+  pub dummy: DummyConfig,
+}
+
+#[derive(Deserialize)]
+pub struct DummyConfig {
+  pub health: f32,
+  pub experience: u64,
+  pub positions: Vec<[f32; 3]>,
+}
+
+impl CombatConfig {
+  const FILE: &'static str = "combat.toml";
+
+  pub fn load() -> Result<Self> {
+    data::load(Self::FILE)
+  }
+
+  fn duration(&self, phase: AttackPhase, attack_speed: f32) -> f32 {
+    let base = match phase {
+      AttackPhase::WindUp => self.windup,
+      AttackPhase::Active => self.active,
+      AttackPhase::Recovery => self.recovery,
+    };
+    base / attack_speed
+  }
+}
+
+// axis aligned box used for hit tests
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+  pub center: Vec3,
+  pub half_extents: Vec3,
+}
+
+impl Aabb {
+  pub fn intersects(&self, other: &Aabb) -> bool {
+    let distance = (self.center - other.center).abs();
+    let reach = self.half_extents + other.half_extents;
+    distance.cmple(reach).all()
+  }
+}
+
+// the volume an entity can be struck in, relative to its transform
+#[derive(Component)]
+pub struct Hurtbox {
+  pub half_extents: Vec3,
+}
+
+impl Hurtbox {
+  pub fn cube(size: f32) -> Self {
+    Self {
+      half_extents: Vec3::splat(size / 2.0),
+    }
+  }
+
+  pub fn at(&self, transform: &Transform) -> Aabb {
+    Aabb {
+      center: transform.translation,
+      half_extents: self.half_extents,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackPhase {
+  WindUp,
+  Active,
+  Recovery,
+}
+
+// a swing in progress, removed once it recovers
+#[derive(Component)]
+pub struct Attack {
+  phase: AttackPhase,
+  timer: Timer,
+  direction: Vec3,
+  attack_speed: f32,
+  // each swing strikes a target at most once
+  struck: Vec<Entity>,
+}
+
+impl Attack {
+  fn new(direction: Vec3, attack_speed: f32, config: &CombatConfig) -> Self {
+    Self {
+      phase: AttackPhase::WindUp,
+      timer: Self::timer(AttackPhase::WindUp, attack_speed, config),
+      direction,
+      attack_speed,
+      struck: Vec::new(),
+    }
+  }
+
+  fn timer(phase: AttackPhase, attack_speed: f32, config: &CombatConfig) -> Timer {
+    Timer::from_seconds(config.duration(phase, attack_speed), TimerMode::Once)
+  }
+
+  fn hitbox(&self, transform: &Transform, config: &CombatConfig) -> Aabb {
+    Aabb {
+      center: transform.translation + self.direction * config.reach,
+      half_extents: Vec3::splat(config.hitbox_size / 2.0),
+    }
+  }
+}
+
+#[derive(Component)]
+pub struct TrainingDummy {
+  experience: u64,
+}
+
+#[derive(Event)]
+pub struct DamageEvent {
+  pub source: Entity,
+  pub target: Entity,
+  // before the target's mitigation
+  pub amount: f32,
+}
+
+type DamageTargets<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static mut Health,
+    Option<&'static Stats>,
+    Option<&'static mut Ward>,
+    Option<&'static TrainingDummy>,
+  ),
+>;
+
+impl DamageEvent {
+  pub fn handler(
+    mut event_reader: EventReader<Self>,
+    mut experience_writer: EventWriter<ExperienceEvent>,
+    mut targets: DamageTargets,
+    players: Query<(), With<PlayerCharacter>>,
+  ) {
+    for event in event_reader.iter() {
+      let Ok((mut health, stats, ward, dummy)) = targets.get_mut(event.target) else {
+        continue;
+      };
+
+      if health.is_empty() {
+        continue;
+      }
+
+      let resistance = stats.map(|stats| stats.resistance).unwrap_or_default();
+      let mut amount = event.amount * (1.0 - resistance);
+      if let Some(mut ward) = ward {
+        amount -= ward.absorb(amount);
+      }
+      health.drain(amount);
+      debug!(
+        "{:?} hit {:?} for {:.1}",
+        event.source, event.target, amount
+      );
+
+      if let Some(dummy) = dummy {
+        if health.is_empty() {
+          if players.contains(event.source) {
+            experience_writer.send(ExperienceEvent {
+              amount: dummy.experience,
+            });
+          }
+          // dummies are never destroyed, just patched up
+          let max = health.max();
+          health.restore(max);
+        }
+      }
+    }
+  }
+}
+
+pub fn spawn_training_dummies(
+  commands: &mut Commands,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<StandardMaterial>,
+  config: &CombatConfig,
+) {
+  let mesh = meshes.add(shape::Cube::new(PLAYER_SIZE).into());
+  let material = materials.add(Color::ORANGE.into());
+  for position in &config.dummy.positions {
+    commands.spawn((
+      TrainingDummy {
+        experience: config.dummy.experience,
+      },
+      Health(Pool::new(config.dummy.health, config.dummy.health)),
+      Hurtbox::cube(PLAYER_SIZE),
+      SessionEntity,
+      PbrBundle {
+        mesh: mesh.clone(),
+        material: material.clone(),
+        transform: Transform::from_translation(Vec3::from_array(*position)),
+        ..default()
+      },
+    ));
+  }
+}
+
+// a player not already mid swing
+type ReadyAttacker<'w, 's> = Query<
+  'w,
+  's,
+  (Entity, &'static Stats, &'static mut Stamina),
+  (With<PlayerCharacter>, Without<Attack>),
+>;
+
+pub fn attack_input(
+  mut commands: Commands,
+  input: ActionInput,
+  config: Res<CombatConfig>,
+  camera: Query<&Front, With<Camera3d>>,
+  mut player: ReadyAttacker,
+) {
+  if !input.just_pressed(Action::Attack) {
+    return;
+  }
+
+  // still swinging
+  let Ok((entity, stats, mut stamina)) = player.get_single_mut() else {
+    return;
+  };
+
+  if !stamina.try_spend(config.stamina_cost) {
+    return;
+  }
+
+  let front = camera.single().direction;
+  let direction = Vec3::new(front.x, front.y, 0.0).normalize_or_zero();
+  commands
+    .entity(entity)
+    .insert(Attack::new(direction, stats.attack_speed, &config));
+}
+
+pub fn advance_attacks(
+  mut commands: Commands,
+  time: Res<Time>,
+  config: Res<CombatConfig>,
+  mut attacks: Query<(Entity, &mut Attack)>,
+) {
+  for (entity, mut attack) in attacks.iter_mut() {
+    if !attack.timer.tick(time.delta()).finished() {
+      continue;
+    }
+
+    let next = match attack.phase {
+      AttackPhase::WindUp => AttackPhase::Active,
+      AttackPhase::Active => AttackPhase::Recovery,
+      AttackPhase::Recovery => {
+        commands.entity(entity).remove::<Attack>();
+        continue;
+      }
+    };
+    attack.timer = Attack::timer(next, attack.attack_speed, &config);
+    attack.phase = next;
+  }
+}
+
+pub fn resolve_hits(
+  config: Res<CombatConfig>,
+  mut damage_writer: EventWriter<DamageEvent>,
+  mut attackers: Query<(Entity, &Transform, &Stats, &mut Attack)>,
+  targets: Query<(Entity, &Transform, &Hurtbox)>,
+) {
+  for (source, transform, stats, mut attack) in attackers.iter_mut() {
+    if attack.phase != AttackPhase::Active {
+      continue;
+    }
+
+    let hitbox = attack.hitbox(transform, &config);
+    for (target, target_transform, hurtbox) in targets.iter() {
+      if target == source || attack.struck.contains(&target) {
+        continue;
+      }
+
+      if hitbox.intersects(&hurtbox.at(target_transform)) {
+        attack.struck.push(target);
+        damage_writer.send(DamageEvent {
+          source,
+          target,
+          amount: stats.attack_power,
+        });
+      }
+    }
+  }
+}
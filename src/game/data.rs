@@ -0,0 +1,19 @@
+use anyhow::{Context, Result};
+use bevy::asset::FileAssetIo;
+use serde::de::DeserializeOwned;
+use std::{fs, path::PathBuf};
+
+// game data lives beside the other assets so it can be tuned without rebuilding
+pub fn path(file: &str) -> PathBuf {
+  FileAssetIo::get_base_path()
+    .join("assets")
+    .join("data")
+    .join(file)
+}
+
+pub fn load<T: DeserializeOwned>(file: &str) -> Result<T> {
+  let path = path(file);
+  let data =
+    fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+  toml::from_str(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
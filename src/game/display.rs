@@ -0,0 +1,247 @@
+use crate::storage::{SystemInformation, WindowMode, WindowSettings};
+use bevy::{
+  prelude::*,
+  window::{PrimaryWindow, WindowPosition},
+  winit::WinitWindows,
+};
+use winit::{dpi::PhysicalSize, monitor::MonitorHandle, window::Fullscreen};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+  pub width: u32,
+  pub height: u32,
+  // millihertz
+  pub refresh_rate: u32,
+}
+
+pub struct Monitor {
+  pub name: String,
+  pub primary: bool,
+  // position among the system's monitors, what bevy selects them by
+  index: usize,
+  // largest first, fastest first within a size
+  video_modes: Vec<VideoMode>,
+}
+
+impl Monitor {
+  // every size the monitor can run at, largest first
+  pub fn resolutions(&self) -> Vec<(u32, u32)> {
+    let mut resolutions = self
+      .video_modes
+      .iter()
+      .map(|mode| (mode.width, mode.height))
+      .collect::<Vec<_>>();
+    resolutions.dedup();
+    resolutions
+  }
+
+  // fastest first
+  pub fn refresh_rates(&self, width: u32, height: u32) -> Vec<u32> {
+    let mut refresh_rates = self
+      .video_modes
+      .iter()
+      .filter(|mode| mode.width == width && mode.height == height)
+      .map(|mode| mode.refresh_rate)
+      .collect::<Vec<_>>();
+    refresh_rates.dedup();
+    refresh_rates
+  }
+}
+
+#[derive(Default, Resource)]
+pub struct Monitors(Vec<Monitor>);
+
+impl Monitors {
+  pub fn iter(&self) -> impl Iterator<Item = &Monitor> {
+    self.0.iter()
+  }
+
+  // the named monitor, or the primary one when it's unnamed or gone
+  pub fn get(&self, name: Option<&str>) -> Option<&Monitor> {
+    name
+      .and_then(|name| self.0.iter().find(|monitor| monitor.name == name))
+      .or_else(|| self.0.iter().find(|monitor| monitor.primary))
+      .or_else(|| self.0.first())
+  }
+
+  // points settings saved on another setup at something this one has
+  pub fn validate(&self, window: &mut WindowSettings) {
+    if self.0.is_empty() {
+      return;
+    }
+
+    if let Some(name) = &window.monitor {
+      if !self.0.iter().any(|monitor| monitor.name == *name) {
+        warn!("monitor {} not found, using the primary monitor", name);
+        window.monitor = None;
+      }
+    }
+
+    // windows can be any size, fullscreen has to be a mode the monitor supports
+    if !matches!(window.mode, WindowMode::Fullscreen) {
+      return;
+    }
+    let Some(monitor) = self.get(window.monitor.as_deref()) else {
+      return;
+    };
+
+    let resolutions = monitor.resolutions();
+    if !resolutions.contains(&(window.width, window.height)) {
+      if let Some(&(width, height)) = resolutions.first() {
+        warn!(
+          "{}x{} not supported by {}, using {}x{}",
+          window.width, window.height, monitor.name, width, height
+        );
+        window.width = width;
+        window.height = height;
+      }
+    }
+
+    if let Some(refresh_rate) = window.refresh_rate {
+      if !monitor
+        .refresh_rates(window.width, window.height)
+        .contains(&refresh_rate)
+      {
+        warn!(
+          "{} mHz not supported at {}x{}, using the highest refresh rate",
+          refresh_rate, window.width, window.height
+        );
+        window.refresh_rate = None;
+      }
+    }
+  }
+
+  // where windowed mode centers the window
+  pub fn position(&self, window: &WindowSettings) -> WindowPosition {
+    let selection = self
+      .get(window.monitor.as_deref())
+      .map(|monitor| MonitorSelection::Index(monitor.index))
+      .unwrap_or(MonitorSelection::Primary);
+    WindowPosition::Centered(selection)
+  }
+}
+
+pub fn refresh_monitors(
+  mut monitors: ResMut<Monitors>,
+  winit_windows: NonSend<WinitWindows>,
+  windows: Query<Entity, With<PrimaryWindow>>,
+) {
+  let Some(winit_window) = windows
+    .get_single()
+    .ok()
+    .and_then(|entity| winit_windows.get_window(entity))
+  else {
+    return;
+  };
+
+  let primary = winit_window.primary_monitor();
+  monitors.0 = winit_window
+    .available_monitors()
+    .enumerate()
+    .map(|(index, handle)| {
+      let mut video_modes = handle
+        .video_modes()
+        .map(|mode| VideoMode {
+          width: mode.size().width,
+          height: mode.size().height,
+          refresh_rate: mode.refresh_rate_millihertz(),
+        })
+        .collect::<Vec<_>>();
+      video_modes.sort_by(|a, b| {
+        (b.width, b.height, b.refresh_rate).cmp(&(a.width, a.height, a.refresh_rate))
+      });
+      // bit depth is the only other difference between modes
+      video_modes.dedup();
+      Monitor {
+        name: handle
+          .name()
+          .unwrap_or_else(|| format!("Monitor {}", index + 1)),
+        primary: primary.as_ref() == Some(&handle),
+        index,
+        video_modes,
+      }
+    })
+    .collect();
+}
+
+// the window is created before monitors are known, so saved choices are checked and applied after
+pub fn apply_saved_window(
+  monitors: Res<Monitors>,
+  mut sys_info: ResMut<SystemInformation>,
+  mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+  monitors.validate(&mut sys_info.settings.window);
+  let settings = &sys_info.settings.window;
+  if let Ok(mut window) = windows.get_single_mut() {
+    window.position = monitors.position(settings);
+    window
+      .resolution
+      .set(settings.width as f32, settings.height as f32);
+  }
+}
+
+// bevy always goes fullscreen on the monitor the window is on, in the mode it thinks is best, so
+// the chosen monitor and video mode are enforced here, after bevy has had its say
+pub fn sync_fullscreen(
+  sys_info: Res<SystemInformation>,
+  monitors: Res<Monitors>,
+  winit_windows: NonSend<WinitWindows>,
+  windows: Query<Entity, With<PrimaryWindow>>,
+) {
+  let settings = &sys_info.settings.window;
+  if matches!(settings.mode, WindowMode::Windowed) {
+    return;
+  }
+  let Some(winit_window) = windows
+    .get_single()
+    .ok()
+    .and_then(|entity| winit_windows.get_window(entity))
+  else {
+    return;
+  };
+  let Some(handle) = monitors
+    .get(settings.monitor.as_deref())
+    .and_then(|monitor| winit_window.available_monitors().nth(monitor.index))
+  else {
+    return;
+  };
+
+  let current = winit_window.fullscreen();
+  let desired = match settings.mode {
+    WindowMode::Windowed => return,
+    WindowMode::Borderless => {
+      if matches!(&current, Some(Fullscreen::Borderless(Some(on))) if *on == handle) {
+        return;
+      }
+      Fullscreen::Borderless(Some(handle))
+    }
+    WindowMode::Fullscreen => {
+      let size = PhysicalSize::new(settings.width, settings.height);
+      let wanted = |mode: &winit::monitor::VideoMode| {
+        mode.size() == size
+          && settings
+            .refresh_rate
+            .is_none_or(|refresh_rate| mode.refresh_rate_millihertz() == refresh_rate)
+      };
+      if matches!(&current, Some(Fullscreen::Exclusive(mode)) if mode.monitor() == handle && wanted(mode))
+      {
+        return;
+      }
+      let Some(mode) = best_mode(&handle, wanted) else {
+        return;
+      };
+      Fullscreen::Exclusive(mode)
+    }
+  };
+  winit_window.set_fullscreen(Some(desired));
+}
+
+fn best_mode(
+  handle: &MonitorHandle,
+  wanted: impl Fn(&winit::monitor::VideoMode) -> bool,
+) -> Option<winit::monitor::VideoMode> {
+  handle
+    .video_modes()
+    .filter(|mode| wanted(mode))
+    .max_by_key(|mode| (mode.refresh_rate_millihertz(), mode.bit_depth()))
+}
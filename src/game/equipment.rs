@@ -0,0 +1,532 @@
+use super::{
+  items::{Inventory, Item, Items},
+  rules::MIN_ATTRIBUTE,
+  Attributes,
+};
+use crate::storage::saves::{AttributeKind, Attributes as SavedAttributes, EquipSlot};
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+use std::{
+  collections::BTreeMap,
+  fmt::{Display, Formatter, Result as FmtResult},
+};
+use strum::IntoEnumIterator;
+
+// what part of the body an item is worn on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipKind {
+  Weapon,
+  Offhand,
+  Head,
+  Chest,
+  Hands,
+  Legs,
+  Feet,
+  Ring,
+}
+
+impl EquipKind {
+  pub fn slots(self) -> &'static [EquipSlot] {
+    match self {
+      EquipKind::Weapon => &[EquipSlot::Weapon],
+      EquipKind::Offhand => &[EquipSlot::Offhand],
+      EquipKind::Head => &[EquipSlot::Head],
+      EquipKind::Chest => &[EquipSlot::Chest],
+      EquipKind::Hands => &[EquipSlot::Hands],
+      EquipKind::Legs => &[EquipSlot::Legs],
+      EquipKind::Feet => &[EquipSlot::Feet],
+      EquipKind::Ring => &[EquipSlot::LeftRing, EquipSlot::RightRing],
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquipError {
+  NotEquipment,
+  WrongSlot(EquipSlot),
+  Requires(AttributeKind, u32),
+  InventoryFull,
+}
+
+impl Display for EquipError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::NotEquipment => write!(f, "cannot be equipped"),
+      Self::WrongSlot(slot) => write!(f, "does not fit the {} slot", slot),
+      Self::Requires(kind, value) => write!(f, "requires {} {}", value, kind),
+      Self::InventoryFull => write!(f, "no free inventory slots"),
+    }
+  }
+}
+
+// requirements are held against the character's own attributes, never bonuses
+pub fn check_requirements(item: &Item, base: &SavedAttributes) -> Result<(), EquipError> {
+  for (&kind, &value) in &item.requirements {
+    if base.get(kind) < value {
+      return Err(EquipError::Requires(kind, value));
+    }
+  }
+  Ok(())
+}
+
+// attributes earned through creation and leveling, what the save stores
+#[derive(Component)]
+pub struct BaseAttributes(pub SavedAttributes);
+
+#[derive(Component, Default)]
+pub struct Equipment {
+  slots: BTreeMap<EquipSlot, String>,
+}
+
+impl Equipment {
+  // an empty slot `item` can be worn in
+  pub fn free_slot(&self, item: &Item) -> Option<EquipSlot> {
+    item
+      .slot?
+      .slots()
+      .iter()
+      .copied()
+      .find(|slot| !self.slots.contains_key(slot))
+  }
+
+  // puts `item` in `slot`, returning the id of what was worn there
+  pub fn equip(
+    &mut self,
+    slot: EquipSlot,
+    item: &Item,
+    base: &SavedAttributes,
+  ) -> Result<Option<String>, EquipError> {
+    let kind = item.slot.ok_or(EquipError::NotEquipment)?;
+    if !kind.slots().contains(&slot) {
+      return Err(EquipError::WrongSlot(slot));
+    }
+    check_requirements(item, base)?;
+    Ok(self.slots.insert(slot, item.id.clone()))
+  }
+
+  pub fn get(&self, slot: EquipSlot) -> Option<&str> {
+    self.slots.get(&slot).map(String::as_str)
+  }
+
+  // wears one item from the inventory stack at `index`, swapping out what the slot held
+  pub fn equip_from_inventory(
+    &mut self,
+    inventory: &mut Inventory,
+    index: usize,
+    items: &Items,
+    base: &SavedAttributes,
+  ) -> Result<EquipSlot, EquipError> {
+    let stack = inventory
+      .stacks()
+      .get(index)
+      .filter(|stack| stack.count > 0)
+      .ok_or(EquipError::NotEquipment)?;
+    let item = items.get(&stack.id).ok_or(EquipError::NotEquipment)?;
+    let kind = item.slot.ok_or(EquipError::NotEquipment)?;
+    let slot = self.free_slot(item).unwrap_or(kind.slots()[0]);
+    check_requirements(item, base)?;
+
+    // the swapped out item needs a slot of its own unless the stack empties
+    let previous = self.get(slot).and_then(|id| items.get(id));
+    if let Some(previous) = previous {
+      let freed = stack.count == 1;
+      if !freed && !inventory.has_room(previous, 1, items) {
+        return Err(EquipError::InventoryFull);
+      }
+    }
+
+    inventory.take_one(index);
+    if let Some(previous) = self.equip(slot, item, base)? {
+      match items.get(&previous) {
+        Some(previous) => inventory.insert(previous, 1),
+        None => warn!("discarded unknown item {}", previous),
+      }
+    }
+    Ok(slot)
+  }
+
+  // moves what is worn in `slot` back into the inventory
+  pub fn unequip_to_inventory(
+    &mut self,
+    slot: EquipSlot,
+    inventory: &mut Inventory,
+    items: &Items,
+  ) -> Result<(), EquipError> {
+    let Some(id) = self.slots.get(&slot) else {
+      return Ok(());
+    };
+
+    match items.get(id) {
+      Some(item) if !inventory.has_room(item, 1, items) => return Err(EquipError::InventoryFull),
+      Some(item) => inventory.insert(item, 1),
+      None => warn!("discarded unknown item {}", id),
+    }
+    self.slots.remove(&slot);
+    Ok(())
+  }
+
+  pub fn weight(&self, items: &Items) -> f32 {
+    self
+      .slots
+      .values()
+      .filter_map(|id| items.get(id))
+      .map(|item| item.weight)
+      .sum()
+  }
+
+  pub fn modifiers<'a>(
+    &'a self,
+    items: &'a Items,
+  ) -> impl Iterator<Item = (AttributeKind, i32)> + 'a {
+    self
+      .slots
+      .values()
+      .filter_map(|id| items.get(id))
+      .flat_map(|item| item.modifiers.iter().map(|(&kind, &amount)| (kind, amount)))
+  }
+}
+
+impl From<BTreeMap<EquipSlot, String>> for Equipment {
+  fn from(slots: BTreeMap<EquipSlot, String>) -> Self {
+    Self { slots }
+  }
+}
+
+impl From<&Equipment> for BTreeMap<EquipSlot, String> {
+  fn from(equipment: &Equipment) -> Self {
+    equipment.slots.clone()
+  }
+}
+
+struct Buff {
+  attribute: AttributeKind,
+  amount: i32,
+  timer: Timer,
+}
+
+// temporary modifiers, lost on save
+#[derive(Component, Default)]
+pub struct Buffs {
+  active: Vec<Buff>,
+}
+
+impl Buffs {
+  pub fn add(&mut self, attribute: AttributeKind, amount: i32, duration: f32) {
+    self.active.push(Buff {
+      attribute,
+      amount,
+      timer: Timer::from_seconds(duration, TimerMode::Once),
+    });
+  }
+
+  pub fn modifiers(&self) -> impl Iterator<Item = (AttributeKind, i32)> + '_ {
+    self.active.iter().map(|buff| (buff.attribute, buff.amount))
+  }
+}
+
+// base + equipment + buffs, never below the creation minimum
+pub fn effective(
+  base: &SavedAttributes,
+  modifiers: impl IntoIterator<Item = (AttributeKind, i32)>,
+) -> Attributes {
+  let mut totals = HashMap::<AttributeKind, i32>::default();
+  for (kind, amount) in modifiers {
+    *totals.entry(kind).or_default() += amount;
+  }
+
+  let mut attributes = base.clone();
+  for kind in AttributeKind::iter() {
+    let value = attributes.get_mut(kind);
+    let total = totals.get(&kind).copied().unwrap_or_default();
+    *value = value.saturating_add_signed(total).max(MIN_ATTRIBUTE);
+  }
+  Attributes::from(attributes)
+}
+
+// characters whose attributes are out of date
+type Modified<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static BaseAttributes,
+    &'static Equipment,
+    &'static Buffs,
+    &'static mut Attributes,
+  ),
+  Or<(Changed<BaseAttributes>, Changed<Equipment>, Changed<Buffs>)>,
+>;
+
+pub fn apply_modifiers(items: Res<Items>, mut query: Modified) {
+  for (base, equipment, buffs, mut attributes) in query.iter_mut() {
+    let modifiers = equipment.modifiers(&items).chain(buffs.modifiers());
+    // stats only recalculate when the result actually differs
+    attributes.set_if_neq(effective(&base.0, modifiers));
+  }
+}
+
+pub fn tick_buffs(time: Res<Time>, mut query: Query<&mut Buffs>) {
+  for mut buffs in query.iter_mut() {
+    // ticking alone should not count as a change
+    let active = &mut buffs.bypass_change_detection().active;
+    let before = active.len();
+    active.retain_mut(|buff| !buff.timer.tick(time.delta()).finished());
+    if active.len() != before {
+      buffs.set_changed();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::storage::saves::ItemStack;
+
+  fn base(value: u32) -> SavedAttributes {
+    let mut attributes = SavedAttributes::default();
+    for kind in AttributeKind::iter() {
+      *attributes.get_mut(kind) = value;
+    }
+    attributes
+  }
+
+  fn carrying(ids: &[(&str, u32)]) -> Inventory {
+    Inventory::from(
+      ids
+        .iter()
+        .map(|&(id, count)| ItemStack {
+          id: id.to_string(),
+          count,
+        })
+        .collect::<Vec<_>>(),
+    )
+  }
+
+  fn counts(inventory: &Inventory) -> Vec<(&str, u32)> {
+    inventory
+      .stacks()
+      .iter()
+      .map(|stack| (stack.id.as_str(), stack.count))
+      .collect()
+  }
+
+  #[test]
+  fn equip_returns_what_was_worn() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let sword = items.get("iron_sword").unwrap();
+    let dagger = items.get("dagger").unwrap();
+
+    assert_eq!(
+      equipment.equip(EquipSlot::Weapon, sword, &base(5)),
+      Ok(None)
+    );
+    assert_eq!(
+      equipment.equip(EquipSlot::Weapon, dagger, &base(5)),
+      Ok(Some("iron_sword".to_string()))
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), Some("dagger"));
+  }
+
+  #[test]
+  fn equip_rejects_the_wrong_slot() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+
+    assert_eq!(
+      equipment.equip(EquipSlot::Chest, items.get("iron_sword").unwrap(), &base(5)),
+      Err(EquipError::WrongSlot(EquipSlot::Chest))
+    );
+    assert_eq!(
+      equipment.equip(
+        EquipSlot::Weapon,
+        items.get("health_potion").unwrap(),
+        &base(5)
+      ),
+      Err(EquipError::NotEquipment)
+    );
+    assert_eq!(equipment.get(EquipSlot::Chest), None);
+    assert_eq!(equipment.get(EquipSlot::Weapon), None);
+  }
+
+  #[test]
+  fn equip_checks_requirements_against_base_attributes() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let sword = items.get("iron_sword").unwrap();
+
+    assert_eq!(
+      equipment.equip(EquipSlot::Weapon, sword, &base(3)),
+      Err(EquipError::Requires(AttributeKind::Strength, 4))
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), None);
+    assert_eq!(
+      equipment.equip(EquipSlot::Weapon, sword, &base(4)),
+      Ok(None)
+    );
+  }
+
+  #[test]
+  fn rings_fill_both_hands_before_swapping() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let mut inventory = carrying(&[("silver_ring", 1), ("silver_ring", 1), ("silver_ring", 1)]);
+
+    let slots: Vec<_> = (0..3)
+      .map(|_| equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)))
+      .collect();
+    assert_eq!(
+      slots,
+      [
+        Ok(EquipSlot::LeftRing),
+        Ok(EquipSlot::RightRing),
+        Ok(EquipSlot::LeftRing)
+      ]
+    );
+    // the third ring swapped the first back out
+    assert_eq!(counts(&inventory), [("silver_ring", 1)]);
+  }
+
+  #[test]
+  fn equip_from_inventory_swaps_the_worn_item_back() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let mut inventory = carrying(&[("iron_sword", 1), ("dagger", 1)]);
+
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)),
+      Ok(EquipSlot::Weapon)
+    );
+    assert_eq!(counts(&inventory), [("dagger", 1)]);
+
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)),
+      Ok(EquipSlot::Weapon)
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), Some("dagger"));
+    assert_eq!(counts(&inventory), [("iron_sword", 1)]);
+  }
+
+  #[test]
+  fn equip_from_inventory_leaves_everything_on_failure() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let mut inventory = carrying(&[("iron_sword", 1), ("health_potion", 2)]);
+
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(3)),
+      Err(EquipError::Requires(AttributeKind::Strength, 4))
+    );
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 1, &items, &base(5)),
+      Err(EquipError::NotEquipment)
+    );
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 2, &items, &base(5)),
+      Err(EquipError::NotEquipment)
+    );
+    assert_eq!(
+      counts(&inventory),
+      [("iron_sword", 1), ("health_potion", 2)]
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), None);
+  }
+
+  #[test]
+  fn swapping_needs_room_unless_the_stack_empties() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    equipment
+      .equip(EquipSlot::Weapon, items.get("dagger").unwrap(), &base(5))
+      .unwrap();
+
+    // a full inventory where taking the sword leaves its stack behind
+    let mut stacks = vec![("iron_sword", 2)];
+    stacks.resize(items.slots, ("gold_coin", 1));
+    let mut inventory = carrying(&stacks);
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)),
+      Err(EquipError::InventoryFull)
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), Some("dagger"));
+    assert_eq!(inventory.count("iron_sword"), 2);
+
+    // a single sword leaves its slot to the dagger
+    let mut stacks = vec![("iron_sword", 1)];
+    stacks.resize(items.slots, ("gold_coin", 1));
+    let mut inventory = carrying(&stacks);
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)),
+      Ok(EquipSlot::Weapon)
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), Some("iron_sword"));
+    assert_eq!(inventory.count("dagger"), 1);
+    assert_eq!(inventory.stacks().len(), items.slots);
+  }
+
+  #[test]
+  fn empty_stacks_cannot_be_equipped() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    let mut inventory = carrying(&[("dagger", 0)]);
+
+    assert_eq!(
+      equipment.equip_from_inventory(&mut inventory, 0, &items, &base(5)),
+      Err(EquipError::NotEquipment)
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), None);
+    assert_eq!(counts(&inventory), [("dagger", 0)]);
+  }
+
+  #[test]
+  fn unequip_needs_a_free_slot() {
+    let items = Items::load().unwrap();
+    let mut equipment = Equipment::default();
+    equipment
+      .equip(EquipSlot::Weapon, items.get("dagger").unwrap(), &base(5))
+      .unwrap();
+
+    let mut inventory = carrying(&vec![("gold_coin", 1); items.slots]);
+    assert_eq!(
+      equipment.unequip_to_inventory(EquipSlot::Weapon, &mut inventory, &items),
+      Err(EquipError::InventoryFull)
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), Some("dagger"));
+
+    let mut inventory = carrying(&[]);
+    assert_eq!(
+      equipment.unequip_to_inventory(EquipSlot::Weapon, &mut inventory, &items),
+      Ok(())
+    );
+    assert_eq!(equipment.get(EquipSlot::Weapon), None);
+    assert_eq!(counts(&inventory), [("dagger", 1)]);
+  }
+
+  #[test]
+  fn effective_adds_up_modifiers() {
+    let attributes = effective(
+      &base(5),
+      [
+        (AttributeKind::Strength, 2),
+        (AttributeKind::Strength, 1),
+        (AttributeKind::Mind, -1),
+      ],
+    );
+    assert_eq!(attributes.get(AttributeKind::Strength), 8);
+    assert_eq!(attributes.get(AttributeKind::Mind), 4);
+    assert_eq!(attributes.get(AttributeKind::Vitality), 5);
+  }
+
+  #[test]
+  fn effective_never_drops_below_the_minimum() {
+    let attributes = effective(
+      &base(5),
+      [(AttributeKind::Agility, -4), (AttributeKind::Wisdom, -100)],
+    );
+    assert_eq!(attributes.get(AttributeKind::Agility), MIN_ATTRIBUTE);
+    assert_eq!(attributes.get(AttributeKind::Wisdom), MIN_ATTRIBUTE);
+
+    let attributes = effective(&base(u32::MAX - 1), [(AttributeKind::Strength, 5)]);
+    assert_eq!(attributes.get(AttributeKind::Strength), u32::MAX);
+  }
+}
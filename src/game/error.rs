@@ -0,0 +1,46 @@
+use std::{
+  fmt::{Display, Formatter, Result as FmtResult},
+  io,
+};
+
+// errors the player can recover from without restarting the game
+#[derive(Debug, Clone)]
+pub enum GameError {
+  SaveUnreadable { id: String, reason: String },
+  SaveCorrupt { id: String, reason: String },
+  // the game was started without saying which character to play
+  NoCharacter,
+}
+
+impl GameError {
+  pub fn from_load(id: String, err: anyhow::Error) -> Self {
+    let reason = err.to_string();
+    if err.downcast_ref::<io::Error>().is_some() {
+      Self::SaveUnreadable { id, reason }
+    } else {
+      Self::SaveCorrupt { id, reason }
+    }
+  }
+
+  // id of the save the error concerns, none when it's about no save in particular
+  pub fn save_id(&self) -> Option<&str> {
+    match self {
+      Self::SaveUnreadable { id, .. } | Self::SaveCorrupt { id, .. } => Some(id),
+      Self::NoCharacter => None,
+    }
+  }
+}
+
+impl Display for GameError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::SaveUnreadable { id, reason } => {
+        write!(f, "Save {} could not be read: {}", id, reason)
+      }
+      Self::SaveCorrupt { id, reason } => {
+        write!(f, "Save {} is corrupt: {}", id, reason)
+      }
+      Self::NoCharacter => write!(f, "The game was started without a character"),
+    }
+  }
+}
@@ -0,0 +1,197 @@
+use super::SessionEntity;
+use crate::storage::SystemInformation;
+use bevy::{
+  pbr::{DirectionalLightShadowMap, PointLightShadowMap},
+  prelude::*,
+  render::{
+    camera::RenderTarget,
+    render_resource::{
+      Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    },
+  },
+  window::PrimaryWindow,
+};
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
+
+// the scaled image the 3d camera renders into and what shows it on the window
+#[derive(Resource)]
+pub struct ScaledRender {
+  image: Handle<Image>,
+  display: Entity,
+  sprite: Entity,
+}
+
+// sleeps off whatever is left of the frame's budget, meant to run last
+pub fn limit_frame_rate(sys_info: Res<SystemInformation>, mut last_frame: Local<Option<Instant>>) {
+  let frame_cap = sys_info.settings.graphics.frame_cap;
+  if let Some(last_frame) = *last_frame {
+    if frame_cap > 0 {
+      let budget = Duration::from_secs_f64(1.0 / frame_cap as f64);
+      let elapsed = last_frame.elapsed();
+      if elapsed < budget {
+        thread::sleep(budget - elapsed);
+      }
+    }
+  }
+  *last_frame = Some(Instant::now());
+}
+
+pub fn sync_shadows(
+  sys_info: Res<SystemInformation>,
+  mut directional_lights: Query<&mut DirectionalLight>,
+  mut point_lights: Query<&mut PointLight>,
+  mut spot_lights: Query<&mut SpotLight>,
+  mut directional_shadow_map: ResMut<DirectionalLightShadowMap>,
+  mut point_shadow_map: ResMut<PointLightShadowMap>,
+) {
+  let graphics = &sys_info.settings.graphics;
+  for mut light in directional_lights.iter_mut() {
+    if light.shadows_enabled != graphics.shadows {
+      light.shadows_enabled = graphics.shadows;
+    }
+  }
+  for mut light in point_lights.iter_mut() {
+    if light.shadows_enabled != graphics.shadows {
+      light.shadows_enabled = graphics.shadows;
+    }
+  }
+  for mut light in spot_lights.iter_mut() {
+    if light.shadows_enabled != graphics.shadows {
+      light.shadows_enabled = graphics.shadows;
+    }
+  }
+
+  // bevy's defaults, doubled
+  let (directional_size, point_size) = if graphics.high_res_shadows {
+    (4096, 2048)
+  } else {
+    (2048, 1024)
+  };
+  if directional_shadow_map.size != directional_size {
+    directional_shadow_map.size = directional_size;
+  }
+  if point_shadow_map.size != point_size {
+    point_shadow_map.size = point_size;
+  }
+}
+
+// renders the 3d camera into an image sized by the render scale, stretched over the window by a
+// second camera, and goes back to rendering straight to the window at a scale of one
+pub fn sync_render_scale(
+  mut commands: Commands,
+  sys_info: Res<SystemInformation>,
+  scaled: Option<Res<ScaledRender>>,
+  mut images: ResMut<Assets<Image>>,
+  windows: Query<&Window, With<PrimaryWindow>>,
+  mut cameras: Query<&mut Camera, With<Camera3d>>,
+  mut sprites: Query<&mut Sprite>,
+) {
+  let Ok(window) = windows.get_single() else {
+    return;
+  };
+  let Ok(mut camera) = cameras.get_single_mut() else {
+    // the session is gone, and its entities with it
+    if scaled.is_some() {
+      commands.remove_resource::<ScaledRender>();
+    }
+    return;
+  };
+
+  let render_scale = sys_info.settings.graphics.render_scale;
+  if render_scale == 1.0 {
+    if let Some(scaled) = scaled {
+      camera.target = RenderTarget::default();
+      commands.entity(scaled.display).despawn();
+      commands.entity(scaled.sprite).despawn();
+      images.remove(&scaled.image);
+      commands.remove_resource::<ScaledRender>();
+    }
+    return;
+  }
+
+  let size = Extent3d {
+    width: ((window.physical_width() as f32 * render_scale) as u32).max(1),
+    height: ((window.physical_height() as f32 * render_scale) as u32).max(1),
+    depth_or_array_layers: 1,
+  };
+  let window_size = Vec2::new(window.width(), window.height());
+
+  let Some(scaled) = scaled else {
+    let image = images.add(scaled_image(size));
+    camera.target = RenderTarget::Image(image.clone());
+    let display = commands
+      .spawn((
+        Camera2dBundle {
+          camera: Camera {
+            order: 1,
+            ..default()
+          },
+          ..default()
+        },
+        SessionEntity,
+      ))
+      .id();
+    let sprite = commands
+      .spawn((
+        SpriteBundle {
+          texture: image.clone(),
+          sprite: Sprite {
+            custom_size: Some(window_size),
+            ..default()
+          },
+          ..default()
+        },
+        SessionEntity,
+      ))
+      .id();
+    commands.insert_resource(ScaledRender {
+      image,
+      display,
+      sprite,
+    });
+    return;
+  };
+
+  // a camera spawned after the scaled render was set up, like a new session's
+  if !matches!(&camera.target, RenderTarget::Image(image) if *image == scaled.image) {
+    camera.target = RenderTarget::Image(scaled.image.clone());
+  }
+
+  if let Some(image) = images.get(&scaled.image) {
+    if image.texture_descriptor.size != size {
+      if let Some(image) = images.get_mut(&scaled.image) {
+        image.resize(size);
+      }
+    }
+  }
+
+  if let Ok(mut sprite) = sprites.get_mut(scaled.sprite) {
+    if sprite.custom_size != Some(window_size) {
+      sprite.custom_size = Some(window_size);
+    }
+  }
+}
+
+fn scaled_image(size: Extent3d) -> Image {
+  let mut image = Image {
+    texture_descriptor: TextureDescriptor {
+      label: Some("scaled_render"),
+      size,
+      dimension: TextureDimension::D2,
+      format: TextureFormat::Bgra8UnormSrgb,
+      mip_level_count: 1,
+      sample_count: 1,
+      usage: TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[],
+    },
+    ..default()
+  };
+  // fills the data to match the size
+  image.resize(size);
+  image
+}
@@ -0,0 +1,217 @@
+use crate::storage::{Action, Binding, Device, LookSettings, StickSettings, SystemInformation};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::hash::Hash;
+use strum::IntoEnumIterator;
+
+#[derive(Clone, Copy)]
+enum Trigger {
+  Pressed,
+  JustPressed,
+}
+
+// actions resolved against whatever the player bound them to
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+  sys_info: Res<'w, SystemInformation>,
+  keys: Res<'w, Input<KeyCode>>,
+  mouse_buttons: Res<'w, Input<MouseButton>>,
+  gamepads: Res<'w, Gamepads>,
+  gamepad_buttons: Res<'w, Input<GamepadButton>>,
+  gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl ActionInput<'_> {
+  pub fn pressed(&self, action: Action) -> bool {
+    self.triggered(action, Trigger::Pressed)
+  }
+
+  pub fn just_pressed(&self, action: Action) -> bool {
+    self.triggered(action, Trigger::JustPressed)
+  }
+
+  // left stick, shaped by the deadzones and response curve
+  pub fn move_stick(&self) -> Vec2 {
+    self.stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)
+  }
+
+  // yaw and pitch to turn the camera by, in degrees, from `mouse_motion` and the right stick
+  pub fn look(&self, mouse_motion: Vec2) -> Vec2 {
+    let settings = &self.sys_info.settings.input;
+    let stick = self.stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+    // pushing the stick up looks up, while moving the mouse up is a negative delta
+    scale(mouse_motion, &settings.mouse_look)
+      + scale(Vec2::new(stick.x, -stick.y), &settings.gamepad_look)
+  }
+
+  fn gamepad(&self) -> Option<Gamepad> {
+    self.gamepads.iter().next()
+  }
+
+  fn triggered(&self, action: Action, trigger: Trigger) -> bool {
+    let input = &self.sys_info.settings.input;
+    Device::iter()
+      .filter_map(|device| input.binding(action, device))
+      .any(|binding| match binding {
+        Binding::Key(key) => check(&self.keys, key, trigger),
+        Binding::Mouse(button) => check(&self.mouse_buttons, button, trigger),
+        Binding::Gamepad(button) => self.gamepad().is_some_and(|gp| {
+          check(
+            &self.gamepad_buttons,
+            GamepadButton::new(gp, button),
+            trigger,
+          )
+        }),
+      })
+  }
+
+  fn stick(&self, x: GamepadAxisType, y: GamepadAxisType) -> Vec2 {
+    let Some(gp) = self.gamepad() else {
+      return Vec2::ZERO;
+    };
+    let axis = |kind| {
+      self
+        .gamepad_axes
+        .get(GamepadAxis::new(gp, kind))
+        .unwrap_or_default()
+    };
+    shape(
+      Vec2::new(axis(x), axis(y)),
+      &self.sys_info.settings.input.sticks,
+    )
+  }
+}
+
+// remaps the tilt between the deadzones onto 0..=1 through the response curve, keeping direction
+fn shape(raw: Vec2, sticks: &StickSettings) -> Vec2 {
+  let tilt = raw.length();
+  if tilt <= sticks.inner_deadzone {
+    return Vec2::ZERO;
+  }
+  let range = (sticks.outer_deadzone - sticks.inner_deadzone).max(f32::EPSILON);
+  let tilt = ((tilt - sticks.inner_deadzone) / range).min(1.0);
+  raw.normalize() * sticks.curve.apply(tilt)
+}
+
+fn scale(delta: Vec2, look: &LookSettings) -> Vec2 {
+  let sign = |invert: bool| if invert { -1.0 } else { 1.0 };
+  delta * look.sensitivity * Vec2::new(sign(look.invert_x), sign(look.invert_y))
+}
+
+fn check<T: Copy + Eq + Hash + Send + Sync>(input: &Input<T>, value: T, trigger: Trigger) -> bool {
+  match trigger {
+    Trigger::Pressed => input.pressed(value),
+    Trigger::JustPressed => input.just_pressed(value),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::storage::ResponseCurve;
+
+  fn sticks(curve: ResponseCurve) -> StickSettings {
+    StickSettings {
+      inner_deadzone: 0.2,
+      outer_deadzone: 0.8,
+      curve,
+    }
+  }
+
+  fn close(a: Vec2, b: Vec2) -> bool {
+    a.abs_diff_eq(b, 1e-5)
+  }
+
+  #[test]
+  fn tilt_inside_the_deadzone_is_ignored() {
+    let sticks = sticks(ResponseCurve::Linear);
+    assert_eq!(shape(Vec2::ZERO, &sticks), Vec2::ZERO);
+    assert_eq!(shape(Vec2::new(0.1, -0.1), &sticks), Vec2::ZERO);
+    assert_eq!(shape(Vec2::new(0.0, 0.2), &sticks), Vec2::ZERO);
+  }
+
+  #[test]
+  fn output_starts_from_zero_at_the_deadzone_edge() {
+    for curve in ResponseCurve::iter() {
+      let shaped = shape(Vec2::new(0.2 + 1e-4, 0.0), &sticks(curve));
+      assert!(shaped.length() < 1e-3);
+    }
+  }
+
+  #[test]
+  fn full_deflection_is_one() {
+    for curve in ResponseCurve::iter() {
+      let sticks = sticks(curve);
+      assert!(close(shape(Vec2::new(0.0, 0.8), &sticks), Vec2::Y));
+      assert!(close(shape(Vec2::new(-1.0, 0.0), &sticks), Vec2::NEG_X));
+      // a corner reads past one on the raw axes but is capped like any other direction
+      let diagonal = shape(Vec2::ONE, &sticks);
+      assert!((diagonal.length() - 1.0).abs() < 1e-5);
+      assert!(close(diagonal.normalize(), Vec2::ONE.normalize()));
+    }
+  }
+
+  #[test]
+  fn linear_curve_is_proportional_between_the_deadzones() {
+    let sticks = sticks(ResponseCurve::Linear);
+    assert!(close(
+      shape(Vec2::new(0.5, 0.0), &sticks),
+      Vec2::new(0.5, 0.0)
+    ));
+    assert!(close(
+      shape(Vec2::new(-0.5, 0.0), &sticks),
+      Vec2::new(-0.5, 0.0)
+    ));
+    assert!(close(
+      shape(Vec2::new(0.0, -0.35), &sticks),
+      Vec2::new(0.0, -0.25)
+    ));
+  }
+
+  #[test]
+  fn quadratic_curve_softens_the_middle_and_keeps_the_sign() {
+    let sticks = sticks(ResponseCurve::Quadratic);
+    assert!(close(
+      shape(Vec2::new(0.5, 0.0), &sticks),
+      Vec2::new(0.25, 0.0)
+    ));
+    assert!(close(
+      shape(Vec2::new(-0.5, 0.0), &sticks),
+      Vec2::new(-0.25, 0.0)
+    ));
+    assert!(close(
+      shape(Vec2::new(0.0, -0.5), &sticks),
+      Vec2::new(0.0, -0.25)
+    ));
+  }
+
+  #[test]
+  fn deadzones_meeting_still_give_a_direction() {
+    let sticks = StickSettings {
+      inner_deadzone: 0.5,
+      outer_deadzone: 0.5,
+      curve: ResponseCurve::Linear,
+    };
+    assert_eq!(shape(Vec2::new(0.4, 0.0), &sticks), Vec2::ZERO);
+    assert!(close(shape(Vec2::new(0.6, 0.0), &sticks), Vec2::X));
+  }
+
+  #[test]
+  fn scale_applies_sensitivity_and_inversion() {
+    let mut look = LookSettings {
+      sensitivity: 2.0,
+      invert_x: false,
+      invert_y: false,
+    };
+    let delta = Vec2::new(3.0, -1.0);
+    assert_eq!(scale(delta, &look), Vec2::new(6.0, -2.0));
+
+    look.invert_x = true;
+    assert_eq!(scale(delta, &look), Vec2::new(-6.0, -2.0));
+
+    look.invert_y = true;
+    assert_eq!(scale(delta, &look), Vec2::new(-6.0, 2.0));
+
+    look.invert_x = false;
+    assert_eq!(scale(delta, &look), Vec2::new(6.0, 2.0));
+  }
+}
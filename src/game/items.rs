@@ -0,0 +1,539 @@
+use super::{
+  data,
+  equipment::{EquipKind, Equipment},
+  input::ActionInput,
+  stats::Stats,
+  Overlay, PlayerCharacter, SessionEntity,
+};
+use crate::storage::{
+  saves::{AttributeKind, ItemStack},
+  Action,
+};
+use anyhow::Result;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::{
+  collections::HashMap,
+  fmt::{Display, Formatter, Result as FmtResult},
+};
+
+#[derive(Deserialize)]
+pub struct Item {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  pub weight: f32,
+  #[serde(default = "Item::default_max_stack")]
+  pub max_stack: u32,
+  #[serde(default)]
+  pub unique: bool,
+  // none for items that cannot be worn
+  #[serde(default)]
+  pub slot: Option<EquipKind>,
+  // applied while equipped
+  #[serde(default)]
+  pub modifiers: HashMap<AttributeKind, i32>,
+  // base attributes needed to equip
+  #[serde(default)]
+  pub requirements: HashMap<AttributeKind, u32>,
+}
+
+impl Item {
+  fn default_max_stack() -> u32 {
+    1
+  }
+}
+
+#[derive(Deserialize)]
+pub struct ItemSpawn {
+  pub id: String,
+  pub count: u32,
+  pub position: [f32; 3],
+}
+
+#[derive(Resource, Deserialize)]
+pub struct Items {
+  pub slots: usize,
+  pub pickup_range: f32,
+  #[serde(rename = "item")]
+  items: Vec<Item>,
+  #[serde(rename = "spawn", default)]
+  spawns: Vec<ItemSpawn>,
+}
+
+impl Items {
+  const FILE: &'static str = "items.toml";
+
+  pub fn load() -> Result<Self> {
+    let items: Self = data::load(Self::FILE)?;
+    for spawn in &items.spawns {
+      if items.get(&spawn.id).is_none() {
+        warn!("spawn refers to unknown item {}", spawn.id);
+      }
+    }
+    Ok(items)
+  }
+
+  pub fn get(&self, id: &str) -> Option<&Item> {
+    self.items.iter().find(|item| item.id == id)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryError {
+  Full,
+  TooHeavy,
+  AlreadyCarried,
+}
+
+impl Display for InventoryError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::Full => write!(f, "no free inventory slots"),
+      Self::TooHeavy => write!(f, "too heavy to carry"),
+      Self::AlreadyCarried => write!(f, "only one can be carried"),
+    }
+  }
+}
+
+#[derive(Component)]
+pub struct Inventory {
+  stacks: Vec<ItemStack>,
+}
+
+impl Inventory {
+  // saved stacks are only trusted as far as items.toml agrees with them
+  pub fn new(stacks: Vec<ItemStack>, items: &Items) -> Self {
+    let mut inventory = Self {
+      stacks: Vec::with_capacity(stacks.len()),
+    };
+    for stack in stacks {
+      let Some(item) = items.get(&stack.id) else {
+        warn!("dropped {} of unknown item {}", stack.count, stack.id);
+        continue;
+      };
+      if stack.count == 0 {
+        warn!("dropped empty stack of {}", stack.id);
+        continue;
+      }
+
+      let max_stack = item.max_stack.max(1);
+      if stack.count > max_stack {
+        warn!(
+          "split {} {} into stacks of {}",
+          stack.count, stack.id, max_stack
+        );
+      }
+      let mut remaining = stack.count;
+      while remaining > 0 {
+        let count = remaining.min(max_stack);
+        inventory.stacks.push(ItemStack {
+          id: stack.id.clone(),
+          count,
+        });
+        remaining -= count;
+      }
+    }
+    inventory
+  }
+
+  pub fn stacks(&self) -> &[ItemStack] {
+    &self.stacks
+  }
+
+  pub fn count(&self, id: &str) -> u32 {
+    self
+      .stacks
+      .iter()
+      .filter(|stack| stack.id == id)
+      .map(|stack| stack.count)
+      .sum()
+  }
+
+  // unknown items weigh nothing so a stale save never locks up the inventory
+  pub fn weight(&self, items: &Items) -> f32 {
+    self
+      .stacks
+      .iter()
+      .filter_map(|stack| {
+        items
+          .get(&stack.id)
+          .map(|item| item.weight * stack.count as f32)
+      })
+      .sum()
+  }
+
+  // slots needed to hold `count` more of `item`
+  fn slots_needed(&self, item: &Item, count: u32) -> usize {
+    let space = self
+      .stacks
+      .iter()
+      .filter(|stack| stack.id == item.id)
+      .map(|stack| item.max_stack.saturating_sub(stack.count))
+      .sum::<u32>();
+    let overflow = count.saturating_sub(space);
+    overflow.div_ceil(item.max_stack.max(1)) as usize
+  }
+
+  // adds all of `count` or nothing
+  pub fn add(
+    &mut self,
+    item: &Item,
+    count: u32,
+    items: &Items,
+    max_weight: f32,
+  ) -> Result<(), InventoryError> {
+    if item.unique && (count > 1 || self.count(&item.id) > 0) {
+      return Err(InventoryError::AlreadyCarried);
+    }
+
+    if self.weight(items) + item.weight * count as f32 > max_weight {
+      return Err(InventoryError::TooHeavy);
+    }
+
+    if !self.has_room(item, count, items) {
+      return Err(InventoryError::Full);
+    }
+
+    self.insert(item, count);
+    Ok(())
+  }
+
+  pub fn has_room(&self, item: &Item, count: u32, items: &Items) -> bool {
+    self.stacks.len() + self.slots_needed(item, count) <= items.slots
+  }
+
+  // adds without checking any limits, for items moved rather than gained
+  pub fn insert(&mut self, item: &Item, count: u32) {
+    let mut remaining = count;
+    for stack in self.stacks.iter_mut().filter(|stack| stack.id == item.id) {
+      let moved = remaining.min(item.max_stack.saturating_sub(stack.count));
+      stack.count += moved;
+      remaining -= moved;
+    }
+
+    while remaining > 0 {
+      let moved = remaining.min(item.max_stack.max(1));
+      self.stacks.push(ItemStack {
+        id: item.id.clone(),
+        count: moved,
+      });
+      remaining -= moved;
+    }
+  }
+
+  // takes a single item out of the stack at `index`, returning its id, none if there is nothing to take
+  pub fn take_one(&mut self, index: usize) -> Option<String> {
+    let stack = self.stacks.get_mut(index)?;
+    stack.count = stack.count.checked_sub(1)?;
+    let id = stack.id.clone();
+    if stack.count == 0 {
+      self.stacks.remove(index);
+    }
+    Some(id)
+  }
+}
+
+impl From<Vec<ItemStack>> for Inventory {
+  fn from(stacks: Vec<ItemStack>) -> Self {
+    Self { stacks }
+  }
+}
+
+impl From<&Inventory> for Vec<ItemStack> {
+  fn from(inventory: &Inventory) -> Self {
+    inventory.stacks.clone()
+  }
+}
+
+// an item lying in the world waiting to be picked up
+#[derive(Component)]
+pub struct WorldItem {
+  id: String,
+  count: u32,
+}
+
+#[derive(Event)]
+pub enum PickupEvent {
+  PickedUp { name: String, count: u32 },
+  Failed { name: String, error: InventoryError },
+}
+
+pub fn spawn_world_items(
+  commands: &mut Commands,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<StandardMaterial>,
+  items: &Items,
+) {
+  let mesh = meshes.add(shape::Cube::new(20.0).into());
+  let material = materials.add(Color::GOLD.into());
+  for spawn in &items.spawns {
+    commands.spawn((
+      WorldItem {
+        id: spawn.id.clone(),
+        count: spawn.count,
+      },
+      SessionEntity,
+      PbrBundle {
+        mesh: mesh.clone(),
+        material: material.clone(),
+        transform: Transform::from_translation(Vec3::from_array(spawn.position)),
+        ..default()
+      },
+    ));
+  }
+}
+
+pub fn pickup_input(
+  mut commands: Commands,
+  input: ActionInput,
+  items: Res<Items>,
+  mut event_writer: EventWriter<PickupEvent>,
+  mut player: Query<(&Transform, &Stats, &Equipment, &mut Inventory), With<PlayerCharacter>>,
+  world_items: Query<(Entity, &Transform, &WorldItem)>,
+) {
+  if !input.just_pressed(Action::Interact) {
+    return;
+  }
+
+  let Ok((transform, stats, equipment, mut inventory)) = player.get_single_mut() else {
+    return;
+  };
+
+  let nearest = world_items
+    .iter()
+    .map(|(entity, item_transform, world_item)| {
+      let distance = item_transform.translation.distance(transform.translation);
+      (entity, world_item, distance)
+    })
+    .filter(|(.., distance)| *distance <= items.pickup_range)
+    .min_by(|(.., a), (.., b)| a.total_cmp(b));
+
+  let Some((entity, world_item, _)) = nearest else {
+    return;
+  };
+
+  let Some(item) = items.get(&world_item.id) else {
+    warn!("unknown item {} in the world", world_item.id);
+    commands.entity(entity).despawn();
+    return;
+  };
+
+  let name = item.name.clone();
+  // worn items count toward what the character can carry
+  let max_weight = stats.carry_weight - equipment.weight(&items);
+  match inventory.add(item, world_item.count, &items, max_weight) {
+    Ok(()) => {
+      commands.entity(entity).despawn();
+      event_writer.send(PickupEvent::PickedUp {
+        name,
+        count: world_item.count,
+      });
+    }
+    Err(error) => event_writer.send(PickupEvent::Failed { name, error }),
+  }
+}
+
+pub fn inventory_input(input: ActionInput, mut next_overlay: ResMut<NextState<Overlay>>) {
+  if input.just_pressed(Action::Inventory) {
+    next_overlay.set(Overlay::Inventory);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const UNLIMITED: f32 = f32::MAX;
+
+  fn counts(inventory: &Inventory) -> Vec<(&str, u32)> {
+    inventory
+      .stacks()
+      .iter()
+      .map(|stack| (stack.id.as_str(), stack.count))
+      .collect()
+  }
+
+  fn filled(items: &Items, id: &str, slots: usize) -> Inventory {
+    let mut inventory = Inventory::from(Vec::new());
+    for _ in 0..slots {
+      inventory.insert(items.get(id).unwrap(), 1);
+    }
+    inventory
+  }
+
+  #[test]
+  fn add_tops_up_stacks_before_opening_new_ones() {
+    let items = Items::load().unwrap();
+    let potion = items.get("health_potion").unwrap();
+    let mut inventory = Inventory::from(Vec::new());
+
+    inventory.add(potion, 7, &items, UNLIMITED).unwrap();
+    inventory.add(potion, 8, &items, UNLIMITED).unwrap();
+    assert_eq!(
+      counts(&inventory),
+      [("health_potion", 10), ("health_potion", 5)]
+    );
+    assert_eq!(inventory.count("health_potion"), 15);
+  }
+
+  #[test]
+  fn add_is_all_or_nothing_when_slots_run_out() {
+    let items = Items::load().unwrap();
+    let potion = items.get("health_potion").unwrap();
+    let mut inventory = filled(&items, "dagger", items.slots - 1);
+
+    // 15 potions need two slots with only one free
+    assert_eq!(
+      inventory.add(potion, 15, &items, UNLIMITED),
+      Err(InventoryError::Full)
+    );
+    assert_eq!(inventory.count("health_potion"), 0);
+    assert_eq!(inventory.stacks().len(), items.slots - 1);
+
+    inventory.add(potion, 10, &items, UNLIMITED).unwrap();
+    assert_eq!(inventory.stacks().len(), items.slots);
+  }
+
+  #[test]
+  fn full_inventories_still_fill_partial_stacks() {
+    let items = Items::load().unwrap();
+    let potion = items.get("health_potion").unwrap();
+    let mut inventory = filled(&items, "dagger", items.slots - 1);
+    inventory.add(potion, 4, &items, UNLIMITED).unwrap();
+
+    assert!(inventory.has_room(potion, 6, &items));
+    assert!(!inventory.has_room(potion, 7, &items));
+  }
+
+  #[test]
+  fn add_respects_the_weight_limit() {
+    let items = Items::load().unwrap();
+    let sword = items.get("iron_sword").unwrap();
+    let mut inventory = Inventory::from(Vec::new());
+
+    assert_eq!(
+      inventory.add(sword, 2, &items, 10.0),
+      Err(InventoryError::TooHeavy)
+    );
+    assert!(inventory.stacks().is_empty());
+
+    // carrying exactly the limit is allowed
+    inventory.add(sword, 1, &items, 6.0).unwrap();
+    assert_eq!(
+      inventory.add(items.get("dagger").unwrap(), 1, &items, 6.0),
+      Err(InventoryError::TooHeavy)
+    );
+    assert_eq!(inventory.weight(&items), 6.0);
+  }
+
+  #[test]
+  fn unknown_items_weigh_nothing() {
+    let items = Items::load().unwrap();
+    let inventory = Inventory::from(vec![
+      ItemStack {
+        id: "removed_item".to_string(),
+        count: 3,
+      },
+      ItemStack {
+        id: "health_potion".to_string(),
+        count: 4,
+      },
+    ]);
+    assert_eq!(inventory.weight(&items), 2.0);
+  }
+
+  #[test]
+  fn unique_items_are_carried_once() {
+    let items = Items::load().unwrap();
+    let amulet = items.get("ancient_amulet").unwrap();
+    let mut inventory = Inventory::from(Vec::new());
+
+    assert_eq!(
+      inventory.add(amulet, 2, &items, UNLIMITED),
+      Err(InventoryError::AlreadyCarried)
+    );
+    assert!(inventory.stacks().is_empty());
+
+    inventory.add(amulet, 1, &items, UNLIMITED).unwrap();
+    assert_eq!(
+      inventory.add(amulet, 1, &items, UNLIMITED),
+      Err(InventoryError::AlreadyCarried)
+    );
+    assert_eq!(inventory.count("ancient_amulet"), 1);
+  }
+
+  fn stack(id: &str, count: u32) -> ItemStack {
+    ItemStack {
+      id: id.to_string(),
+      count,
+    }
+  }
+
+  #[test]
+  fn loading_drops_empty_and_unknown_stacks() {
+    let items = Items::load().unwrap();
+    let inventory = Inventory::new(
+      vec![
+        stack("dagger", 0),
+        stack("removed_item", 3),
+        stack("health_potion", 4),
+      ],
+      &items,
+    );
+    assert_eq!(counts(&inventory), [("health_potion", 4)]);
+  }
+
+  #[test]
+  fn loading_splits_oversized_stacks() {
+    let items = Items::load().unwrap();
+    let inventory = Inventory::new(
+      vec![
+        stack("health_potion", 25),
+        stack("dagger", 2),
+        stack("gold_coin", 9999),
+      ],
+      &items,
+    );
+    assert_eq!(
+      counts(&inventory),
+      [
+        ("health_potion", 10),
+        ("health_potion", 10),
+        ("health_potion", 5),
+        ("dagger", 1),
+        ("dagger", 1),
+        ("gold_coin", 9999)
+      ]
+    );
+  }
+
+  #[test]
+  fn take_one_from_an_empty_stack_is_none() {
+    let mut inventory = Inventory::from(vec![stack("dagger", 0)]);
+    assert_eq!(inventory.take_one(0), None);
+    assert_eq!(counts(&inventory), [("dagger", 0)]);
+  }
+
+  #[test]
+  fn take_one_empties_stacks_one_at_a_time() {
+    let items = Items::load().unwrap();
+    let mut inventory = Inventory::from(Vec::new());
+    inventory
+      .add(items.get("mana_potion").unwrap(), 2, &items, UNLIMITED)
+      .unwrap();
+    inventory
+      .add(items.get("dagger").unwrap(), 1, &items, UNLIMITED)
+      .unwrap();
+
+    assert_eq!(inventory.take_one(0).as_deref(), Some("mana_potion"));
+    assert_eq!(counts(&inventory), [("mana_potion", 1), ("dagger", 1)]);
+
+    // the emptied stack goes away and the rest shift down
+    assert_eq!(inventory.take_one(0).as_deref(), Some("mana_potion"));
+    assert_eq!(counts(&inventory), [("dagger", 1)]);
+
+    assert_eq!(inventory.take_one(1), None);
+    assert_eq!(counts(&inventory), [("dagger", 1)]);
+  }
+}
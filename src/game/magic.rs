@@ -0,0 +1,319 @@
+use super::{
+  combat::{Aabb, DamageEvent, Hurtbox},
+  data,
+  equipment::Buffs,
+  input::ActionInput,
+  stats::Stats,
+  vitals::Mana,
+  Front, PlayerCharacter, SessionEntity,
+};
+use crate::storage::{
+  saves::{AttributeKind, SpellsState},
+  Action,
+};
+use anyhow::Result;
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpellEffect {
+  // a projectile damaging the first thing it hits
+  Bolt {
+    amount: f32,
+    scaling: f32,
+    speed: f32,
+    range: f32,
+    size: f32,
+  },
+  // absorbs damage until depleted or expired
+  Ward {
+    amount: f32,
+    scaling: f32,
+    duration: f32,
+  },
+  // moves the caster forward instantly
+  Blink {
+    distance: f32,
+  },
+  // raises one of the caster's attributes for a while
+  Empower {
+    attribute: AttributeKind,
+    amount: i32,
+    duration: f32,
+  },
+}
+
+#[derive(Deserialize)]
+pub struct Spell {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  pub mana_cost: f32,
+  // seconds
+  pub cooldown: f32,
+  pub effect: SpellEffect,
+}
+
+impl Spell {
+  pub fn cost(&self, stats: &Stats) -> f32 {
+    self.mana_cost * (1.0 - stats.mana_efficiency)
+  }
+}
+
+#[derive(Resource, Deserialize)]
+pub struct Spells {
+  #[serde(rename = "spell")]
+  spells: Vec<Spell>,
+}
+
+impl Spells {
+  const FILE: &'static str = "spells.toml";
+
+  pub fn load() -> Result<Self> {
+    data::load(Self::FILE)
+  }
+
+  pub fn get(&self, id: &str) -> Option<&Spell> {
+    self.spells.iter().find(|spell| spell.id == id)
+  }
+}
+
+#[derive(Component)]
+pub struct Spellbook {
+  known: Vec<String>,
+  memorized: Vec<String>,
+  // seconds remaining, by spell id
+  cooldowns: HashMap<String, f32>,
+}
+
+impl Spellbook {
+  // the spells that fit in `slots`, in slot order
+  pub fn castable(&self, slots: u32) -> &[String] {
+    &self.memorized[..self.memorized.len().min(slots as usize)]
+  }
+
+  pub fn cooldown(&self, id: &str) -> f32 {
+    self.cooldowns.get(id).copied().unwrap_or_default()
+  }
+}
+
+impl From<SpellsState> for Spellbook {
+  fn from(state: SpellsState) -> Self {
+    Self {
+      known: state.known,
+      memorized: state.memorized,
+      cooldowns: HashMap::default(),
+    }
+  }
+}
+
+impl From<&Spellbook> for SpellsState {
+  fn from(spellbook: &Spellbook) -> Self {
+    Self {
+      known: spellbook.known.clone(),
+      memorized: spellbook.memorized.clone(),
+    }
+  }
+}
+
+#[derive(Component)]
+pub struct Projectile {
+  source: Entity,
+  direction: Vec3,
+  speed: f32,
+  // distance left before it fizzles
+  range: f32,
+  size: f32,
+  damage: f32,
+}
+
+#[derive(Component)]
+pub struct Ward {
+  remaining: f32,
+  timer: Timer,
+}
+
+impl Ward {
+  // the part of `amount` the ward soaks up
+  pub fn absorb(&mut self, amount: f32) -> f32 {
+    let absorbed = amount.min(self.remaining);
+    self.remaining -= absorbed;
+    absorbed
+  }
+}
+
+const SLOT_ACTIONS: [Action; 4] = [
+  Action::Spell1,
+  Action::Spell2,
+  Action::Spell3,
+  Action::Spell4,
+];
+
+type Caster<'w, 's> = Query<
+  'w,
+  's,
+  (
+    Entity,
+    &'static mut Transform,
+    &'static Stats,
+    &'static mut Mana,
+    &'static mut Spellbook,
+    &'static mut Buffs,
+  ),
+  With<PlayerCharacter>,
+>;
+
+pub fn cast_input(
+  mut commands: Commands,
+  input: ActionInput,
+  spells: Res<Spells>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<StandardMaterial>>,
+  camera: Query<&Front, With<Camera3d>>,
+  mut player: Caster,
+) {
+  let Some(slot) = SLOT_ACTIONS
+    .iter()
+    .position(|&action| input.just_pressed(action))
+  else {
+    return;
+  };
+
+  let Ok((caster, mut transform, stats, mut mana, mut spellbook, mut buffs)) =
+    player.get_single_mut()
+  else {
+    return;
+  };
+
+  let Some(spell) = spellbook
+    .castable(stats.spell_slots)
+    .get(slot)
+    .and_then(|id| spells.get(id))
+  else {
+    return;
+  };
+
+  if spellbook.cooldown(&spell.id) > 0.0 || !mana.try_spend(spell.cost(stats)) {
+    return;
+  }
+
+  let front = camera.single().direction;
+  let direction = Vec3::new(front.x, front.y, 0.0).normalize_or_zero();
+
+  match spell.effect {
+    SpellEffect::Bolt {
+      amount,
+      scaling,
+      speed,
+      range,
+      size,
+    } => {
+      commands.spawn((
+        Projectile {
+          source: caster,
+          direction,
+          speed,
+          range,
+          size,
+          damage: amount + scaling * stats.spell_power,
+        },
+        SessionEntity,
+        PbrBundle {
+          mesh: meshes.add(
+            shape::UVSphere {
+              radius: size / 2.0,
+              ..default()
+            }
+            .into(),
+          ),
+          material: materials.add(Color::ORANGE_RED.into()),
+          transform: Transform::from_translation(transform.translation),
+          ..default()
+        },
+      ));
+    }
+    SpellEffect::Ward {
+      amount,
+      scaling,
+      duration,
+    } => {
+      commands.entity(caster).insert(Ward {
+        remaining: amount + scaling * stats.spell_power,
+        timer: Timer::from_seconds(duration, TimerMode::Once),
+      });
+    }
+    SpellEffect::Blink { distance } => {
+      transform.translation += direction * distance;
+    }
+    SpellEffect::Empower {
+      attribute,
+      amount,
+      duration,
+    } => {
+      buffs.add(attribute, amount, duration);
+    }
+  }
+
+  info!("cast {}", spell.name);
+  spellbook.cooldowns.insert(spell.id.clone(), spell.cooldown);
+}
+
+pub fn tick_cooldowns(time: Res<Time>, mut spellbooks: Query<&mut Spellbook>) {
+  let delta = time.delta_seconds();
+  for mut spellbook in spellbooks.iter_mut() {
+    if spellbook.cooldowns.is_empty() {
+      continue;
+    }
+    spellbook.cooldowns.retain(|_, remaining| {
+      *remaining -= delta;
+      *remaining > 0.0
+    });
+  }
+}
+
+pub fn move_projectiles(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut damage_writer: EventWriter<DamageEvent>,
+  mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+  targets: Query<(Entity, &Transform, &Hurtbox), Without<Projectile>>,
+) {
+  for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+    let step = projectile.speed * time.delta_seconds();
+    transform.translation += projectile.direction * step;
+    projectile.range -= step;
+
+    let bounds = Aabb {
+      center: transform.translation,
+      half_extents: Vec3::splat(projectile.size / 2.0),
+    };
+    let hit = targets.iter().find(|(target, target_transform, hurtbox)| {
+      *target != projectile.source && bounds.intersects(&hurtbox.at(target_transform))
+    });
+
+    if let Some((target, ..)) = hit {
+      damage_writer.send(DamageEvent {
+        source: projectile.source,
+        target,
+        amount: projectile.damage,
+      });
+    }
+
+    if hit.is_some() || projectile.range <= 0.0 {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+pub fn expire_wards(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut wards: Query<(Entity, &mut Ward)>,
+) {
+  for (entity, mut ward) in wards.iter_mut() {
+    if ward.timer.tick(time.delta()).finished() || ward.remaining <= 0.0 {
+      commands.entity(entity).remove::<Ward>();
+    }
+  }
+}
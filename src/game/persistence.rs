@@ -0,0 +1,279 @@
+use super::{
+  equipment::{BaseAttributes, Equipment},
+  input::ActionInput,
+  items::Inventory,
+  magic::Spellbook,
+  progression::Progression,
+  vitals::{Health, Mana, Stamina},
+  Class, EulerAngles, GameInfo, Name, PlayTime, PlayerCharacter, SaveId,
+};
+use crate::storage::{
+  saves::{CameraState, PlayerState, SaveData, SaveDataBuilder, VitalsState},
+  settings::SaveSettings,
+  Action, SystemInformation,
+};
+use anyhow::Result;
+use bevy::{
+  app::AppExit,
+  ecs::system::SystemParam,
+  prelude::*,
+  tasks::{IoTaskPool, Task},
+};
+use futures_lite::future;
+use std::time::Duration;
+
+type SavedPlayer<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static SaveId,
+    &'static Name,
+    &'static Class,
+    &'static BaseAttributes,
+    &'static Transform,
+    &'static Health,
+    &'static Stamina,
+    &'static Mana,
+    &'static Progression,
+    &'static Spellbook,
+    &'static Inventory,
+    &'static Equipment,
+  ),
+  With<PlayerCharacter>,
+>;
+
+// everything needed to rebuild the player's session from the ecs
+#[derive(SystemParam)]
+pub struct SaveSnapshot<'w, 's> {
+  player: SavedPlayer<'w, 's>,
+  camera: Query<'w, 's, &'static EulerAngles, With<Camera3d>>,
+  game_info: Option<Res<'w, GameInfo>>,
+  play_time: Option<Res<'w, PlayTime>>,
+}
+
+impl SaveSnapshot<'_, '_> {
+  // the save id and data of the session, none when there is no session to save
+  pub fn take(&self) -> Option<(String, SaveData)> {
+    let (
+      id,
+      name,
+      class,
+      attributes,
+      transform,
+      health,
+      stamina,
+      mana,
+      progression,
+      spellbook,
+      inventory,
+      equipment,
+    ) = self.player.get_single().ok()?;
+    let angles = self.camera.get_single().ok()?;
+    let game_info = self.game_info.as_ref()?;
+    let play_time = self.play_time.as_ref()?;
+
+    Some((
+      id.0.clone(),
+      SaveDataBuilder::new()
+        .name(name.0.clone())
+        .attributes(attributes.0.clone())
+        .class(class.0.clone())
+        .play_time(play_time.0)
+        .player(PlayerState {
+          translation: transform.translation.to_array(),
+          rotation: transform.rotation.to_array(),
+        })
+        .camera(CameraState {
+          mode: game_info.camera_mode,
+          yaw: angles.yaw,
+          pitch: angles.pitch,
+        })
+        .vitals(VitalsState {
+          health: health.current(),
+          stamina: stamina.current(),
+          mana: mana.current(),
+        })
+        .progression(progression.into())
+        .spells(spellbook.into())
+        .inventory(inventory.into())
+        .equipment(equipment.into())
+        .build(),
+    ))
+  }
+
+  // for when the app may be gone before a background save could finish
+  fn save_blocking(&self, sys_info: &SystemInformation) {
+    if let Some((id, save_data)) = self.take() {
+      let file_path = sys_info.save_path(&id);
+      match save_data.save(&file_path) {
+        Ok(()) => info!("saved {} to {}", save_data.name, file_path.display()),
+        Err(e) => error!("failed to save {}: {}", save_data.name, e),
+      }
+    }
+  }
+}
+
+// lives as long as the session, so trips to the settings menu don't restart the countdown
+#[derive(Resource)]
+pub struct AutosaveTimer(Timer);
+
+impl AutosaveTimer {
+  pub fn new(settings: &SaveSettings) -> Self {
+    Self(Timer::new(Self::interval(settings), TimerMode::Repeating))
+  }
+
+  fn interval(settings: &SaveSettings) -> Duration {
+    Duration::from_secs(settings.autosave_interval as u64)
+  }
+}
+
+#[derive(Component)]
+pub struct SaveTask {
+  name: String,
+  // none once waited out by `FinalSave`, the entity is on its way out
+  task: Option<Task<Result<()>>>,
+}
+
+#[derive(Event)]
+pub struct SaveGameEvent;
+
+#[derive(Event)]
+pub struct SaveCompletedEvent {
+  pub name: String,
+}
+
+#[derive(Event)]
+pub struct SaveFailedEvent {
+  pub name: String,
+  pub error: String,
+}
+
+impl SaveGameEvent {
+  pub fn handler(
+    mut commands: Commands,
+    mut event_reader: EventReader<Self>,
+    mut pending: Local<bool>,
+    snapshot: SaveSnapshot,
+    sys_info: Res<SystemInformation>,
+    tasks: Query<(), With<SaveTask>>,
+  ) {
+    // any number of requests collapse into a single save
+    if event_reader.iter().last().is_some() {
+      *pending = true;
+    }
+
+    // wait for the previous save to land so saves are written in order
+    if !*pending || !tasks.is_empty() {
+      return;
+    }
+    *pending = false;
+
+    let Some((id, save_data)) = snapshot.take() else {
+      warn!("nothing to save, no player in the world");
+      return;
+    };
+
+    let name = save_data.name.clone();
+    let file_path = sys_info.save_path(&id);
+    let task = IoTaskPool::get().spawn(async move { save_data.save(&file_path) });
+    commands.spawn(SaveTask {
+      name,
+      task: Some(task),
+    });
+  }
+}
+
+pub fn autosave_system(
+  time: Res<Time>,
+  sys_info: Res<SystemInformation>,
+  mut timer: ResMut<AutosaveTimer>,
+  mut event_writer: EventWriter<SaveGameEvent>,
+) {
+  // a changed interval keeps the time already counted
+  let interval = AutosaveTimer::interval(&sys_info.settings.saves);
+  if timer.0.duration() != interval {
+    timer.0.set_duration(interval);
+  }
+
+  if timer.0.duration().is_zero() {
+    return;
+  }
+
+  if timer.0.tick(time.delta()).just_finished() {
+    event_writer.send(SaveGameEvent);
+  }
+}
+
+pub fn manual_save_input(input: ActionInput, mut event_writer: EventWriter<SaveGameEvent>) {
+  if input.just_pressed(Action::QuickSave) {
+    event_writer.send(SaveGameEvent);
+  }
+}
+
+// a save for when the session is about to end, after whatever is still being written
+#[derive(SystemParam)]
+pub struct FinalSave<'w, 's> {
+  commands: Commands<'w, 's>,
+  snapshot: SaveSnapshot<'w, 's>,
+  tasks: Query<'w, 's, (Entity, &'static mut SaveTask)>,
+}
+
+impl FinalSave<'_, '_> {
+  pub fn save(&mut self, sys_info: &SystemInformation) {
+    // waits out saves still being written, so one started earlier can't land after this one
+    for (entity, mut save) in self.tasks.iter_mut() {
+      let Some(task) = save.task.take() else {
+        continue;
+      };
+      match future::block_on(task) {
+        Ok(()) => info!("saved {}", save.name),
+        Err(e) => error!("failed to save {}: {}", save.name, e),
+      }
+      self.commands.entity(entity).despawn();
+    }
+    self.snapshot.save_blocking(sys_info);
+  }
+}
+
+// the window's close button never goes through the pause menu, so every way out saves here
+pub fn save_on_exit(
+  mut exit_reader: EventReader<AppExit>,
+  mut final_save: FinalSave,
+  sys_info: Res<SystemInformation>,
+) {
+  if exit_reader.iter().last().is_none() {
+    return;
+  }
+
+  final_save.save(&sys_info);
+}
+
+pub fn poll_save_tasks(
+  mut commands: Commands,
+  mut tasks: Query<(Entity, &mut SaveTask)>,
+  mut completed_writer: EventWriter<SaveCompletedEvent>,
+  mut failed_writer: EventWriter<SaveFailedEvent>,
+) {
+  for (entity, mut save) in tasks.iter_mut() {
+    let Some(task) = &mut save.task else {
+      continue;
+    };
+    if let Some(result) = future::block_on(future::poll_once(task)) {
+      let name = std::mem::take(&mut save.name);
+      match result {
+        Ok(()) => {
+          info!("saved {}", name);
+          completed_writer.send(SaveCompletedEvent { name });
+        }
+        Err(e) => {
+          error!("failed to save {}: {}", name, e);
+          failed_writer.send(SaveFailedEvent {
+            name,
+            error: e.to_string(),
+          });
+        }
+      }
+      commands.entity(entity).despawn();
+    }
+  }
+}
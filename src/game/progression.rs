@@ -0,0 +1,207 @@
+use super::{data, input::ActionInput, Overlay, PlayerCharacter};
+use crate::storage::{saves::ProgressionState, Action};
+use anyhow::Result;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Resource, Deserialize)]
+pub struct ProgressionConfig {
+  pub base: f32,
+  pub growth: f32,
+  pub max_level: u32,
+  pub points_per_level: u32,
+  pub max_attribute: u32,
+}
+
+impl ProgressionConfig {
+  const FILE: &'static str = "progression.toml";
+
+  pub fn load() -> Result<Self> {
+    data::load(Self::FILE)
+  }
+
+  // experience needed to advance from `level`, none at the level cap
+  pub fn experience_to_next(&self, level: u32) -> Option<u64> {
+    (level < self.max_level).then(|| (self.base * (level as f32).powf(self.growth)).max(1.0) as u64)
+  }
+}
+
+#[derive(Component)]
+pub struct Progression {
+  level: u32,
+  // earned toward the next level
+  experience: u64,
+  unspent_points: u32,
+}
+
+impl Progression {
+  pub fn level(&self) -> u32 {
+    self.level
+  }
+
+  pub fn experience(&self) -> u64 {
+    self.experience
+  }
+
+  pub fn unspent_points(&self) -> u32 {
+    self.unspent_points
+  }
+
+  // adds experience, returning the number of levels gained
+  pub fn gain(&mut self, amount: u64, config: &ProgressionConfig) -> u32 {
+    let start = self.level;
+    self.experience += amount;
+    while let Some(needed) = config.experience_to_next(self.level) {
+      if self.experience < needed {
+        break;
+      }
+      self.experience -= needed;
+      self.level += 1;
+      self.unspent_points += config.points_per_level;
+    }
+
+    // nothing left to earn at the cap
+    if config.experience_to_next(self.level).is_none() {
+      self.experience = 0;
+    }
+
+    self.level - start
+  }
+
+  // spends `points` only if all of them are available
+  pub fn spend(&mut self, points: u32) -> bool {
+    if points > self.unspent_points {
+      return false;
+    }
+    self.unspent_points -= points;
+    true
+  }
+}
+
+impl From<ProgressionState> for Progression {
+  fn from(state: ProgressionState) -> Self {
+    Self {
+      level: state.level.max(1),
+      experience: state.experience,
+      unspent_points: state.unspent_points,
+    }
+  }
+}
+
+impl From<&Progression> for ProgressionState {
+  fn from(progression: &Progression) -> Self {
+    Self {
+      level: progression.level,
+      experience: progression.experience,
+      unspent_points: progression.unspent_points,
+    }
+  }
+}
+
+#[derive(Event)]
+pub struct ExperienceEvent {
+  pub amount: u64,
+}
+
+#[derive(Event)]
+pub struct LevelUpEvent {
+  pub level: u32,
+}
+
+impl ExperienceEvent {
+  pub fn handler(
+    mut event_reader: EventReader<Self>,
+    mut level_up_writer: EventWriter<LevelUpEvent>,
+    config: Res<ProgressionConfig>,
+    mut query: Query<&mut Progression, With<PlayerCharacter>>,
+  ) {
+    let Ok(mut progression) = query.get_single_mut() else {
+      return;
+    };
+
+    for event in event_reader.iter() {
+      if progression.gain(event.amount, &config) > 0 {
+        info!("reached level {}", progression.level);
+        level_up_writer.send(LevelUpEvent {
+          level: progression.level,
+        });
+      }
+    }
+  }
+}
+
+pub fn level_up_input(input: ActionInput, mut next_overlay: ResMut<NextState<Overlay>>) {
+  if input.just_pressed(Action::LevelUp) {
+    next_overlay.set(Overlay::LevelUp);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 100 to leave level 1, 200 to leave level 2, 300 to leave level 3
+  const CONFIG: ProgressionConfig = ProgressionConfig {
+    base: 100.0,
+    growth: 1.0,
+    max_level: 4,
+    points_per_level: 2,
+    max_attribute: 30,
+  };
+
+  fn fresh() -> Progression {
+    ProgressionState {
+      level: 1,
+      experience: 0,
+      unspent_points: 0,
+    }
+    .into()
+  }
+
+  #[test]
+  fn experience_below_the_threshold_carries_over() {
+    let mut progression = fresh();
+    assert_eq!(progression.gain(60, &CONFIG), 0);
+    assert_eq!(progression.gain(30, &CONFIG), 0);
+    assert_eq!((progression.level(), progression.experience()), (1, 90));
+    assert_eq!(progression.unspent_points(), 0);
+  }
+
+  #[test]
+  fn one_gain_can_cross_several_levels() {
+    let mut progression = fresh();
+    assert_eq!(progression.gain(350, &CONFIG), 2);
+    assert_eq!((progression.level(), progression.experience()), (3, 50));
+    assert_eq!(progression.unspent_points(), 4);
+  }
+
+  #[test]
+  fn gains_stop_at_the_level_cap() {
+    let mut progression = fresh();
+    assert_eq!(progression.gain(10_000, &CONFIG), 3);
+    assert_eq!((progression.level(), progression.experience()), (4, 0));
+    assert_eq!(progression.unspent_points(), 6);
+
+    // nothing accumulates once capped
+    assert_eq!(progression.gain(500, &CONFIG), 0);
+    assert_eq!((progression.level(), progression.experience()), (4, 0));
+    assert_eq!(progression.unspent_points(), 6);
+  }
+
+  #[test]
+  fn no_experience_is_needed_past_the_cap() {
+    assert_eq!(CONFIG.experience_to_next(3), Some(300));
+    assert_eq!(CONFIG.experience_to_next(4), None);
+    assert_eq!(CONFIG.experience_to_next(5), None);
+  }
+
+  #[test]
+  fn spend_is_all_or_nothing() {
+    let mut progression = fresh();
+    progression.gain(100, &CONFIG);
+    assert!(!progression.spend(3));
+    assert_eq!(progression.unspent_points(), 2);
+    assert!(progression.spend(2));
+    assert_eq!(progression.unspent_points(), 0);
+  }
+}
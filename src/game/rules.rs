@@ -0,0 +1,242 @@
+use crate::storage::saves::{AttributeKind, Attributes};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use strum::IntoEnumIterator;
+
+// points available to a new character on top of the minimum in every attribute
+pub const STARTING_POINTS: u32 = 16;
+pub const MIN_ATTRIBUTE: u32 = 1;
+pub const MAX_ATTRIBUTE: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+  BelowMinimum(AttributeKind),
+  AboveMaximum(AttributeKind),
+  Overspent(u32),
+  Unspent(u32),
+}
+
+impl Display for BuildError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      BuildError::BelowMinimum(kind) => write!(f, "{} must be at least {}", kind, MIN_ATTRIBUTE),
+      BuildError::AboveMaximum(kind) => write!(f, "{} can be at most {}", kind, MAX_ATTRIBUTE),
+      BuildError::Overspent(points) => write!(f, "{} points too many spent", points),
+      BuildError::Unspent(points) => write!(f, "{} points left to spend", points),
+    }
+  }
+}
+
+// allocation of the starting point pool across the eight attributes
+#[derive(Clone)]
+pub struct PointBuy {
+  attributes: Attributes,
+}
+
+impl Default for PointBuy {
+  fn default() -> Self {
+    Self {
+      attributes: Attributes {
+        vitality: MIN_ATTRIBUTE,
+        endurance: MIN_ATTRIBUTE,
+        strength: MIN_ATTRIBUTE,
+        dexterity: MIN_ATTRIBUTE,
+        agility: MIN_ATTRIBUTE,
+        intelligence: MIN_ATTRIBUTE,
+        wisdom: MIN_ATTRIBUTE,
+        mind: MIN_ATTRIBUTE,
+      },
+    }
+  }
+}
+
+impl From<Attributes> for PointBuy {
+  fn from(attributes: Attributes) -> Self {
+    Self { attributes }
+  }
+}
+
+impl PointBuy {
+  pub fn attributes(&self) -> &Attributes {
+    &self.attributes
+  }
+
+  pub fn get(&self, kind: AttributeKind) -> u32 {
+    self.attributes.get(kind)
+  }
+
+  pub fn spent(&self) -> u32 {
+    AttributeKind::iter()
+      .map(|kind| self.get(kind).saturating_sub(MIN_ATTRIBUTE))
+      .sum()
+  }
+
+  pub fn remaining(&self) -> u32 {
+    STARTING_POINTS.saturating_sub(self.spent())
+  }
+
+  pub fn can_increase(&self, kind: AttributeKind) -> bool {
+    self.get(kind) < MAX_ATTRIBUTE && self.remaining() > 0
+  }
+
+  pub fn can_decrease(&self, kind: AttributeKind) -> bool {
+    self.get(kind) > MIN_ATTRIBUTE
+  }
+
+  pub fn increase(&mut self, kind: AttributeKind) {
+    if self.can_increase(kind) {
+      *self.attributes.get_mut(kind) += 1;
+    }
+  }
+
+  pub fn decrease(&mut self, kind: AttributeKind) {
+    if self.can_decrease(kind) {
+      *self.attributes.get_mut(kind) -= 1;
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), BuildError> {
+    validate(&self.attributes)
+  }
+}
+
+pub fn validate(attributes: &Attributes) -> Result<(), BuildError> {
+  for kind in AttributeKind::iter() {
+    let value = attributes.get(kind);
+    if value < MIN_ATTRIBUTE {
+      return Err(BuildError::BelowMinimum(kind));
+    }
+    if value > MAX_ATTRIBUTE {
+      return Err(BuildError::AboveMaximum(kind));
+    }
+  }
+
+  let spent: u32 = AttributeKind::iter()
+    .map(|kind| attributes.get(kind) - MIN_ATTRIBUTE)
+    .sum();
+  if spent > STARTING_POINTS {
+    Err(BuildError::Overspent(spent - STARTING_POINTS))
+  } else if spent < STARTING_POINTS {
+    Err(BuildError::Unspent(STARTING_POINTS - spent))
+  } else {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn maxed(kind: AttributeKind) -> PointBuy {
+    let mut build = PointBuy::default();
+    for _ in MIN_ATTRIBUTE..MAX_ATTRIBUTE {
+      build.increase(kind);
+    }
+    build
+  }
+
+  #[test]
+  fn starts_at_the_minimum_with_every_point_left() {
+    let build = PointBuy::default();
+    for kind in AttributeKind::iter() {
+      assert_eq!(build.get(kind), MIN_ATTRIBUTE);
+    }
+    assert_eq!(build.spent(), 0);
+    assert_eq!(build.remaining(), STARTING_POINTS);
+  }
+
+  #[test]
+  fn spending_and_refunding_move_single_points() {
+    let mut build = PointBuy::default();
+    build.increase(AttributeKind::Strength);
+    assert_eq!(build.get(AttributeKind::Strength), MIN_ATTRIBUTE + 1);
+    assert_eq!(build.remaining(), STARTING_POINTS - 1);
+
+    build.decrease(AttributeKind::Strength);
+    assert_eq!(build.get(AttributeKind::Strength), MIN_ATTRIBUTE);
+    assert_eq!(build.remaining(), STARTING_POINTS);
+  }
+
+  #[test]
+  fn cannot_refund_below_the_minimum() {
+    let mut build = PointBuy::default();
+    assert!(!build.can_decrease(AttributeKind::Mind));
+    build.decrease(AttributeKind::Mind);
+    assert_eq!(build.get(AttributeKind::Mind), MIN_ATTRIBUTE);
+    assert_eq!(build.remaining(), STARTING_POINTS);
+  }
+
+  #[test]
+  fn cannot_spend_above_the_maximum() {
+    let mut build = maxed(AttributeKind::Agility);
+    assert_eq!(build.get(AttributeKind::Agility), MAX_ATTRIBUTE);
+    assert!(!build.can_increase(AttributeKind::Agility));
+
+    let remaining = build.remaining();
+    build.increase(AttributeKind::Agility);
+    assert_eq!(build.get(AttributeKind::Agility), MAX_ATTRIBUTE);
+    assert_eq!(build.remaining(), remaining);
+
+    // the top of the range refunds like any other point
+    assert!(build.can_decrease(AttributeKind::Agility));
+  }
+
+  #[test]
+  fn cannot_spend_more_than_the_pool() {
+    let mut build = PointBuy::default();
+    for kind in AttributeKind::iter() {
+      for _ in MIN_ATTRIBUTE..MAX_ATTRIBUTE {
+        build.increase(kind);
+      }
+    }
+    assert_eq!(build.spent(), STARTING_POINTS);
+    assert_eq!(build.remaining(), 0);
+    assert!(AttributeKind::iter().all(|kind| !build.can_increase(kind)));
+  }
+
+  #[test]
+  fn validate_requires_every_point_spent() {
+    let mut build = PointBuy::default();
+    assert_eq!(build.validate(), Err(BuildError::Unspent(STARTING_POINTS)));
+
+    for kind in AttributeKind::iter().take(2) {
+      for _ in MIN_ATTRIBUTE..MAX_ATTRIBUTE {
+        build.increase(kind);
+      }
+    }
+    build.increase(AttributeKind::Mind);
+    assert_eq!(build.validate(), Err(BuildError::Unspent(1)));
+
+    build.increase(AttributeKind::Mind);
+    assert_eq!(build.remaining(), 0);
+    assert_eq!(build.validate(), Ok(()));
+  }
+
+  #[test]
+  fn validate_rejects_out_of_range_attributes() {
+    let mut attributes = maxed(AttributeKind::Vitality).attributes().clone();
+    attributes.vitality = MAX_ATTRIBUTE + 1;
+    assert_eq!(
+      validate(&attributes),
+      Err(BuildError::AboveMaximum(AttributeKind::Vitality))
+    );
+
+    attributes.vitality = MIN_ATTRIBUTE - 1;
+    assert_eq!(
+      validate(&attributes),
+      Err(BuildError::BelowMinimum(AttributeKind::Vitality))
+    );
+  }
+
+  #[test]
+  fn validate_rejects_overspent_attributes() {
+    let mut attributes = Attributes::default();
+    for kind in AttributeKind::iter() {
+      *attributes.get_mut(kind) = MAX_ATTRIBUTE;
+    }
+    let spent = (MAX_ATTRIBUTE - MIN_ATTRIBUTE) * 8;
+    assert_eq!(
+      validate(&attributes),
+      Err(BuildError::Overspent(spent - STARTING_POINTS))
+    );
+  }
+}
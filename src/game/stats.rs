@@ -0,0 +1,323 @@
+use super::{data, Attributes};
+use anyhow::{anyhow, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Curve {
+  // base + scale * points ^ exponent
+  Power {
+    base: f32,
+    scale: f32,
+    exponent: f32,
+  },
+  // approaches `max` as points grow, reaching half of it at `half` points
+  Diminishing {
+    max: f32,
+    half: f32,
+  },
+}
+
+impl Curve {
+  pub fn eval(&self, points: u32) -> f32 {
+    let points = points as f32;
+    match *self {
+      Curve::Power {
+        base,
+        scale,
+        exponent,
+      } => base + scale * points.powf(exponent),
+      Curve::Diminishing { max, half } => {
+        if points + half <= 0.0 {
+          0.0
+        } else {
+          max * points / (points + half)
+        }
+      }
+    }
+  }
+
+  // stats never shrink as attributes grow, and never stop being numbers
+  fn validate(&self) -> Result<()> {
+    match *self {
+      Curve::Power {
+        base,
+        scale,
+        exponent,
+      } => {
+        if !(base.is_finite() && scale.is_finite() && exponent.is_finite()) {
+          return Err(anyhow!("power curve values must be finite"));
+        }
+        if scale < 0.0 || exponent < 0.0 {
+          return Err(anyhow!("power curve scale and exponent cannot be negative"));
+        }
+      }
+      Curve::Diminishing { max, half } => {
+        if !(max.is_finite() && half.is_finite()) {
+          return Err(anyhow!("diminishing curve values must be finite"));
+        }
+        if max < 0.0 || half <= 0.0 {
+          return Err(anyhow!(
+            "diminishing curve max cannot be negative and half must be positive"
+          ));
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Resource, Deserialize)]
+pub struct StatCurves {
+  pub max_health: Curve,
+  pub max_stamina: Curve,
+  pub max_mana: Curve,
+  pub resistance: Curve,
+  pub attack_power: Curve,
+  pub attack_speed: Curve,
+  pub carry_weight: Curve,
+  pub move_speed: Curve,
+  pub spell_power: Curve,
+  pub mana_efficiency: Curve,
+  pub spell_slots: Curve,
+}
+
+impl StatCurves {
+  const FILE: &'static str = "stats.toml";
+
+  pub fn load() -> Result<Self> {
+    Self::validated(data::load(Self::FILE)?)
+  }
+
+  fn validated(curves: Self) -> Result<Self> {
+    for (name, curve) in curves.named() {
+      curve
+        .validate()
+        .map_err(|e| anyhow!("{} in {}: {}", name, Self::FILE, e))?;
+    }
+    Ok(curves)
+  }
+
+  fn named(&self) -> [(&'static str, &Curve); 11] {
+    [
+      ("max_health", &self.max_health),
+      ("max_stamina", &self.max_stamina),
+      ("max_mana", &self.max_mana),
+      ("resistance", &self.resistance),
+      ("attack_power", &self.attack_power),
+      ("attack_speed", &self.attack_speed),
+      ("carry_weight", &self.carry_weight),
+      ("move_speed", &self.move_speed),
+      ("spell_power", &self.spell_power),
+      ("mana_efficiency", &self.mana_efficiency),
+      ("spell_slots", &self.spell_slots),
+    ]
+  }
+}
+
+// values derived from attributes, never saved, always recomputed
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+  pub max_health: f32,
+  pub max_stamina: f32,
+  pub max_mana: f32,
+  pub resistance: f32,
+  pub attack_power: f32,
+  pub attack_speed: f32,
+  pub carry_weight: f32,
+  pub move_speed: f32,
+  pub spell_power: f32,
+  pub mana_efficiency: f32,
+  pub spell_slots: u32,
+}
+
+impl Stats {
+  pub fn derive(attributes: &Attributes, curves: &StatCurves) -> Self {
+    Self {
+      max_health: curves.max_health.eval(attributes.vitality),
+      max_stamina: curves.max_stamina.eval(attributes.endurance),
+      max_mana: curves.max_mana.eval(attributes.wisdom),
+      resistance: curves.resistance.eval(attributes.endurance).clamp(0.0, 1.0),
+      attack_power: curves.attack_power.eval(attributes.strength),
+      attack_speed: curves.attack_speed.eval(attributes.dexterity).max(0.1),
+      carry_weight: curves.carry_weight.eval(attributes.strength),
+      move_speed: curves.move_speed.eval(attributes.agility),
+      spell_power: curves.spell_power.eval(attributes.intelligence),
+      mana_efficiency: curves
+        .mana_efficiency
+        .eval(attributes.wisdom)
+        .clamp(0.0, 1.0),
+      spell_slots: curves.spell_slots.eval(attributes.mind).max(0.0) as u32,
+    }
+  }
+}
+
+pub fn recalculate_stats(curves: Res<StatCurves>, mut query: Query<(Ref<Attributes>, &mut Stats)>) {
+  for (attributes, mut stats) in query.iter_mut() {
+    if attributes.is_changed() || curves.is_changed() {
+      let derived = Stats::derive(&attributes, &curves);
+      // avoid tripping change detection for systems watching stats
+      stats.set_if_neq(derived);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    game::rules::{MAX_ATTRIBUTE, MIN_ATTRIBUTE},
+    storage::saves::{AttributeKind, Attributes as SavedAttributes},
+  };
+  use strum::IntoEnumIterator;
+
+  const POWER: Curve = Curve::Power {
+    base: 10.0,
+    scale: 2.0,
+    exponent: 1.5,
+  };
+  const DIMINISHING: Curve = Curve::Diminishing {
+    max: 0.8,
+    half: 10.0,
+  };
+
+  fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-4 * b.abs().max(1.0)
+  }
+
+  #[test]
+  fn power_curve_at_the_bounds() {
+    assert!(close(POWER.eval(0), 10.0));
+    assert!(close(POWER.eval(MIN_ATTRIBUTE), 12.0));
+    assert!(close(
+      POWER.eval(MAX_ATTRIBUTE),
+      10.0 + 2.0 * (MAX_ATTRIBUTE as f32).powf(1.5)
+    ));
+  }
+
+  #[test]
+  fn power_curve_out_of_range() {
+    assert!(close(POWER.eval(100), 2010.0));
+    assert!(POWER.eval(u32::MAX).is_finite());
+  }
+
+  #[test]
+  fn diminishing_curve_at_the_bounds() {
+    assert_eq!(DIMINISHING.eval(0), 0.0);
+    assert!(close(DIMINISHING.eval(MIN_ATTRIBUTE), 0.8 / 11.0));
+    assert!(close(
+      DIMINISHING.eval(MAX_ATTRIBUTE),
+      0.8 * MAX_ATTRIBUTE as f32 / (MAX_ATTRIBUTE as f32 + 10.0)
+    ));
+  }
+
+  #[test]
+  fn diminishing_curve_out_of_range() {
+    assert!(close(DIMINISHING.eval(10), 0.4));
+    let far = DIMINISHING.eval(u32::MAX);
+    assert!(far.is_finite() && far <= 0.8);
+    assert!(close(far, 0.8));
+  }
+
+  fn fields(stats: &Stats) -> [f32; 11] {
+    [
+      stats.max_health,
+      stats.max_stamina,
+      stats.max_mana,
+      stats.resistance,
+      stats.attack_power,
+      stats.attack_speed,
+      stats.carry_weight,
+      stats.move_speed,
+      stats.spell_power,
+      stats.mana_efficiency,
+      stats.spell_slots as f32,
+    ]
+  }
+
+  #[test]
+  fn derived_stats_never_shrink_as_attributes_grow() {
+    let curves = StatCurves::load().unwrap();
+    for kind in AttributeKind::iter() {
+      let mut saved = SavedAttributes::default();
+      let mut previous: Option<[f32; 11]> = None;
+      for value in 0..=100 {
+        *saved.get_mut(kind) = value;
+        let stats = fields(&Stats::derive(&saved.clone().into(), &curves));
+        assert!(stats.iter().all(|stat| stat.is_finite()));
+        if let Some(previous) = previous {
+          for (field, (before, after)) in previous.iter().zip(stats).enumerate() {
+            assert!(
+              after >= *before,
+              "stat {} shrank when {} went to {}",
+              field,
+              kind,
+              value
+            );
+          }
+        }
+        previous = Some(stats);
+      }
+    }
+  }
+
+  #[test]
+  fn derived_stats_stay_in_their_ranges() {
+    let curves = StatCurves::load().unwrap();
+    let mut saved = SavedAttributes::default();
+    for kind in AttributeKind::iter() {
+      *saved.get_mut(kind) = 1000;
+    }
+    let stats = Stats::derive(&saved.into(), &curves);
+    assert!((0.0..=1.0).contains(&stats.resistance));
+    assert!((0.0..=1.0).contains(&stats.mana_efficiency));
+    assert!(stats.attack_speed >= 0.1);
+  }
+
+  fn parse(max_health: &str) -> Result<StatCurves> {
+    let shipped = std::fs::read_to_string(data::path(StatCurves::FILE)).unwrap();
+    let mut table: toml::Table = toml::from_str(&shipped).unwrap();
+    table.insert(
+      "max_health".to_string(),
+      toml::from_str(max_health).unwrap(),
+    );
+    StatCurves::validated(table.try_into()?)
+  }
+
+  #[test]
+  fn accepts_the_shipped_curves() {
+    assert!(parse("kind = \"power\"\nbase = 80.0\nscale = 20.0\nexponent = 1.0").is_ok());
+  }
+
+  #[test]
+  fn rejects_shrinking_power_curves() {
+    assert!(parse("kind = \"power\"\nbase = 80.0\nscale = -20.0\nexponent = 1.0").is_err());
+    assert!(parse("kind = \"power\"\nbase = 80.0\nscale = 20.0\nexponent = -1.0").is_err());
+  }
+
+  #[test]
+  fn rejects_non_finite_curves() {
+    assert!(parse("kind = \"power\"\nbase = nan\nscale = 20.0\nexponent = 1.0").is_err());
+    assert!(parse("kind = \"diminishing\"\nmax = inf\nhalf = 10.0").is_err());
+  }
+
+  #[test]
+  fn rejects_bad_diminishing_curves() {
+    assert!(parse("kind = \"diminishing\"\nmax = 0.5\nhalf = 0.0").is_err());
+    assert!(parse("kind = \"diminishing\"\nmax = -0.5\nhalf = 10.0").is_err());
+  }
+
+  #[test]
+  fn rejects_unknown_curve_kinds() {
+    assert!(parse("kind = \"linear\"\nslope = 1.0").is_err());
+  }
+
+  #[test]
+  fn names_the_bad_curve() {
+    let err = parse("kind = \"diminishing\"\nmax = 0.5\nhalf = 0.0")
+      .err()
+      .unwrap();
+    assert!(err.to_string().contains("max_health"));
+  }
+}
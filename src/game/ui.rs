@@ -1,46 +1,253 @@
 pub mod character_creation;
 pub mod character_selection;
+pub mod error_modal;
+pub mod inventory;
+pub mod level_up;
 pub mod main_menu;
+pub mod pause_menu;
 pub mod settings_menu;
 pub mod ui_playground;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::{
+  egui::{self, Color32},
+  EguiContexts,
+};
 
-use crate::storage::{SystemInformation, WindowMode};
+use crate::{
+  game::{
+    display::Monitors,
+    equipment::Equipment,
+    items::{Inventory, Items, PickupEvent},
+    magic::{Spellbook, Spells},
+    persistence::{SaveCompletedEvent, SaveFailedEvent},
+    progression::{LevelUpEvent, Progression, ProgressionConfig},
+    stats::Stats,
+    vitals::{Health, Mana, Pool, Stamina},
+    PlayerCharacter,
+  },
+  storage::SystemInformation,
+};
 
 #[derive(Event)]
-pub enum WindowEvent {
-  Resize(u32, u32),
-  ModeChange(WindowMode),
-}
+pub struct ApplySettingsEvent;
 
-impl WindowEvent {
+impl ApplySettingsEvent {
+  // pushes the committed settings out to the window and renderer, everything else reads them live
   pub fn handler(
-    mut windows: Query<&mut Window>,
-    mut event_reader: EventReader<WindowEvent>,
-    mut sys_info: ResMut<SystemInformation>,
+    mut commands: Commands,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut event_reader: EventReader<ApplySettingsEvent>,
+    sys_info: Res<SystemInformation>,
+    monitors: Res<Monitors>,
   ) {
-    let mut window = windows.single_mut();
-    for event in event_reader.into_iter() {
-      match *event {
-        WindowEvent::Resize(width, height) => {
-          sys_info.settings.window.width = width;
-          sys_info.settings.window.height = height;
-          window.resolution.set(width as f32, height as f32);
-        }
-        WindowEvent::ModeChange(mode) => {
-          sys_info.settings.window.mode = mode;
-          window.mode.set(Box::new(mode.to_bevy())).ok();
-        }
-      }
+    if event_reader.iter().last().is_none() {
+      return;
     }
+
+    let settings = &sys_info.settings;
+    if let Ok(mut window) = windows.get_single_mut() {
+      // bevy only acts on what differs from the window as it is
+      window.mode = settings.window.mode.to_bevy();
+      window.position = monitors.position(&settings.window);
+      window
+        .resolution
+        .set(settings.window.width as f32, settings.window.height as f32);
+      window.present_mode = settings.graphics.present_mode.into();
+    }
+    commands.insert_resource(settings.graphics.msaa());
   }
 }
 
 // main game ui
 
-pub fn on_enter() {}
+const NOTIFICATION_SECONDS: f32 = 3.0;
+
+#[derive(Default, Resource)]
+pub struct Hud {
+  notification: Option<(String, Timer)>,
+}
+
+impl Hud {
+  fn notify(&mut self, message: String) {
+    self.notification = Some((
+      message,
+      Timer::from_seconds(NOTIFICATION_SECONDS, TimerMode::Once),
+    ));
+  }
+}
+
+pub fn on_enter(mut commands: Commands) {
+  commands.insert_resource(Hud::default());
+}
+
+// turns what happened this frame into the notification the hud shows
+pub fn notify(
+  mut hud: ResMut<Hud>,
+  mut save_completed: EventReader<SaveCompletedEvent>,
+  mut save_failed: EventReader<SaveFailedEvent>,
+  mut level_up: EventReader<LevelUpEvent>,
+  mut pickups: EventReader<PickupEvent>,
+) {
+  for event in pickups.iter() {
+    match event {
+      PickupEvent::PickedUp { name, count } if *count > 1 => {
+        hud.notify(format!("Picked up {} x{}", name, count))
+      }
+      PickupEvent::PickedUp { name, .. } => hud.notify(format!("Picked up {}", name)),
+      PickupEvent::Failed { name, error } => {
+        hud.notify(format!("Cannot pick up {}: {}", name, error))
+      }
+    }
+  }
 
-pub fn on_update() {}
+  for event in level_up.iter() {
+    hud.notify(format!(
+      "Reached level {}, press L to spend points",
+      event.level
+    ));
+  }
 
-pub fn on_exit() {}
+  for event in save_completed.iter() {
+    hud.notify(format!("Saved {}", event.name));
+  }
+
+  for event in save_failed.iter() {
+    hud.notify(format!("Failed to save {}: {}", event.name, event.error));
+  }
+}
+
+type HudPlayer<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static Health,
+    &'static Stamina,
+    &'static Mana,
+    &'static Progression,
+    &'static Stats,
+    &'static Spellbook,
+    &'static Inventory,
+    &'static Equipment,
+  ),
+  With<PlayerCharacter>,
+>;
+
+pub fn on_update(
+  time: Res<Time>,
+  mut contexts: EguiContexts,
+  mut hud: ResMut<Hud>,
+  config: Res<ProgressionConfig>,
+  spells: Res<Spells>,
+  items: Res<Items>,
+  player: HudPlayer,
+) {
+  if let Ok((health, stamina, mana, progression, stats, spellbook, inventory, equipment)) =
+    player.get_single()
+  {
+    egui::Area::new("hud_vitals")
+      .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0))
+      .show(contexts.ctx_mut(), |ui| {
+        ui.set_width(200.0);
+        vital_bar(ui, "Health", health, Color32::DARK_RED);
+        vital_bar(ui, "Stamina", stamina, Color32::DARK_GREEN);
+        vital_bar(ui, "Mana", mana, Color32::DARK_BLUE);
+        ui.label(level_label(progression, &config));
+        ui.label(format!(
+          "Carrying {:.1} / {:.1}",
+          inventory.weight(&items) + equipment.weight(&items),
+          stats.carry_weight
+        ));
+      });
+
+    egui::Area::new("hud_spells")
+      .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
+      .show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+          let castable = spellbook.castable(stats.spell_slots);
+          for (slot, id) in castable.iter().enumerate() {
+            let Some(spell) = spells.get(id) else {
+              continue;
+            };
+            let cooldown = spellbook.cooldown(&spell.id);
+            let label = if cooldown > 0.0 {
+              format!("{} {} ({:.0}s)", slot + 1, spell.name, cooldown.ceil())
+            } else {
+              format!("{} {} ({:.0} mp)", slot + 1, spell.name, spell.cost(stats))
+            };
+            ui.label(label).on_hover_text(&spell.description);
+          }
+        });
+      });
+  }
+
+  let expired = match &mut hud.notification {
+    Some((message, timer)) => {
+      egui::Area::new("hud_notification")
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+        .show(contexts.ctx_mut(), |ui| {
+          ui.label(message.as_str());
+        });
+      timer.tick(time.delta()).finished()
+    }
+    None => false,
+  };
+
+  if expired {
+    hud.notification = None;
+  }
+}
+
+pub fn on_exit(mut commands: Commands) {
+  commands.remove_resource::<Hud>();
+}
+
+fn vital_bar(ui: &mut egui::Ui, label: &str, pool: &Pool, color: Color32) {
+  ui.add(
+    egui::ProgressBar::new(pool.fraction())
+      .fill(color)
+      .text(format!(
+        "{} {:.0} / {:.0}",
+        label,
+        pool.current(),
+        pool.max()
+      )),
+  );
+}
+
+fn level_label(progression: &Progression, config: &ProgressionConfig) -> String {
+  match config.experience_to_next(progression.level()) {
+    Some(needed) => format!(
+      "Level {}  {} / {} xp",
+      progression.level(),
+      progression.experience(),
+      needed
+    ),
+    None => format!("Level {}", progression.level()),
+  }
+}
+
+// derived stats as label and value rows
+pub fn stats_grid(ui: &mut egui::Ui, stats: &Stats) {
+  egui::Grid::new("stats").show(ui, |ui| {
+    let mut row = |label: &str, value: String| {
+      ui.label(label);
+      ui.label(value);
+      ui.end_row();
+    };
+    row("Health", format!("{:.0}", stats.max_health));
+    row("Stamina", format!("{:.0}", stats.max_stamina));
+    row("Mana", format!("{:.0}", stats.max_mana));
+    row("Resistance", format!("{:.0}%", stats.resistance * 100.0));
+    row("Attack power", format!("{:.1}", stats.attack_power));
+    row("Attack speed", format!("{:.2}x", stats.attack_speed));
+    row("Carry weight", format!("{:.1}", stats.carry_weight));
+    row("Move speed", format!("{:.1}", stats.move_speed));
+    row("Spell power", format!("{:.1}", stats.spell_power));
+    row(
+      "Mana efficiency",
+      format!("{:.0}%", stats.mana_efficiency * 100.0),
+    );
+    row("Spell slots", stats.spell_slots.to_string());
+  });
+}
@@ -1,15 +1,57 @@
 use bevy::prelude::*;
-use bevy_egui::{egui, EguiContexts};
+use bevy_egui::{
+  egui::{self, Color32},
+  EguiContexts,
+};
+use strum::IntoEnumIterator;
 
-use crate::game::{GameState, StartGameEvent};
+use crate::{
+  game::{
+    classes::ClassPresets,
+    equipment,
+    items::Items,
+    rules::{PointBuy, MAX_ATTRIBUTE, STARTING_POINTS},
+    stats::{StatCurves, Stats},
+    GameState, StartGameEvent,
+  },
+  storage::{
+    saves::{self, names, AttributeKind, SaveDataBuilder, SpellsState},
+    SystemInformation,
+  },
+};
 
 #[derive(Default, Resource)]
 pub struct CharacterCreationMenu {
   name: String,
+  build: PointBuy,
+  // id of the chosen class preset
+  class: Option<String>,
+  // names of existing characters
+  taken: Vec<String>,
 }
 
-pub fn on_enter(mut commands: Commands) {
-  commands.insert_resource(CharacterCreationMenu::default())
+impl CharacterCreationMenu {
+  fn select_class(&mut self, presets: &ClassPresets, id: &str) {
+    if let Some(preset) = presets.get(id) {
+      self.class = Some(preset.id.clone());
+      self.build = PointBuy::from(preset.attributes.clone());
+    }
+  }
+}
+
+pub fn on_enter(
+  mut commands: Commands,
+  sys_info: Res<SystemInformation>,
+  presets: Res<ClassPresets>,
+) {
+  let mut menu = CharacterCreationMenu {
+    taken: saves::names(&sys_info.game_saves_path),
+    ..default()
+  };
+  if let Some(first) = presets.iter().next() {
+    menu.select_class(&presets, &first.id);
+  }
+  commands.insert_resource(menu)
 }
 
 pub fn on_update(
@@ -17,20 +59,144 @@ pub fn on_update(
   mut event_writer: EventWriter<StartGameEvent>,
   mut contexts: EguiContexts,
   mut menu: ResMut<CharacterCreationMenu>,
+  presets: Res<ClassPresets>,
+  curves: Res<StatCurves>,
+  items: Res<Items>,
 ) {
-  egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
-    if ui.button("New Character").clicked() {
-      next_state.set(GameState::CharacterCreate);
+  let ctx = contexts.ctx_mut();
+
+  egui::SidePanel::right("class_description")
+    .resizable(false)
+    .min_width(240.0)
+    .show(ctx, |ui| {
+      let Some(preset) = menu.class.as_deref().and_then(|id| presets.get(id)) else {
+        ui.label("Choose a class");
+        return;
+      };
+
+      ui.heading(&preset.name);
+      ui.label(&preset.description);
+
+      ui.separator();
+
+      ui.label("Starting equipment");
+      for item in &preset.starting_items {
+        ui.label(format!("  {}", item));
+      }
+
+      if !preset.starting_spells.is_empty() {
+        ui.label("Starting spells");
+        for spell in &preset.starting_spells {
+          ui.label(format!("  {}", spell));
+        }
+      }
+    });
+
+  egui::CentralPanel::default().show(ctx, |ui| {
+    if ui.button("Back").clicked() {
+      next_state.set(GameState::CharacterSelect);
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+      ui.label("Class");
+      for preset in presets.iter() {
+        let selected = menu.class.as_deref() == Some(preset.id.as_str());
+        if ui.selectable_label(selected, &preset.name).clicked() {
+          menu.select_class(&presets, &preset.id);
+        }
+      }
+    });
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+      ui.label("Name");
+      ui.text_edit_singleline(&mut menu.name);
+    });
+
+    let name = menu.name.trim().to_string();
+    let name_validation = names::validate(&name, menu.taken.iter().map(String::as_str));
+    if let Err(e) = &name_validation {
+      ui.colored_label(Color32::RED, e.to_string());
     }
 
     ui.separator();
 
-    ui.text_edit_singleline(&mut menu.name);
+    ui.label(format!(
+      "Points remaining: {} / {}",
+      menu.build.remaining(),
+      STARTING_POINTS
+    ));
+
+    egui::Grid::new("attributes").show(ui, |ui| {
+      for kind in AttributeKind::iter() {
+        ui.label(kind.to_string());
+        if ui
+          .add_enabled(menu.build.can_decrease(kind), egui::Button::new("-"))
+          .clicked()
+        {
+          menu.build.decrease(kind);
+        }
+        ui.label(format!("{} / {}", menu.build.get(kind), MAX_ATTRIBUTE));
+        if ui
+          .add_enabled(menu.build.can_increase(kind), egui::Button::new("+"))
+          .clicked()
+        {
+          menu.build.increase(kind);
+        }
+        ui.end_row();
+      }
+    });
+
+    ui.separator();
+
+    // derived the same way gameplay derives them, starting equipment included
+    let kit = menu
+      .class
+      .as_deref()
+      .and_then(|id| presets.get(id))
+      .map(|preset| preset.starting_kit(&items).0)
+      .unwrap_or_default();
+    let preview = Stats::derive(
+      &equipment::effective(menu.build.attributes(), kit.modifiers(&items)),
+      &curves,
+    );
+    super::stats_grid(ui, &preview);
+
+    ui.separator();
+
+    let validation = menu.build.validate();
+    if let Err(e) = &validation {
+      ui.colored_label(Color32::RED, e.to_string());
+    }
 
-    if ui.button("Create").clicked() {
-      event_writer.send(StartGameEvent {
-        name: menu.name.clone(),
-      });
+    let can_create = validation.is_ok() && name_validation.is_ok();
+    if ui
+      .add_enabled(can_create, egui::Button::new("Create"))
+      .clicked()
+    {
+      let preset = menu.class.as_deref().and_then(|id| presets.get(id));
+      let starting_spells = preset
+        .map(|preset| preset.starting_spells.clone())
+        .unwrap_or_default();
+      let (equipment, inventory) = preset
+        .map(|preset| preset.starting_kit(&items))
+        .unwrap_or_default();
+      event_writer.send(StartGameEvent::Create(Box::new(
+        SaveDataBuilder::new()
+          .name(name)
+          .attributes(menu.build.attributes().clone())
+          .class(menu.class.clone())
+          .spells(SpellsState {
+            known: starting_spells.clone(),
+            memorized: starting_spells,
+          })
+          .inventory(inventory)
+          .equipment((&equipment).into())
+          .build(),
+      )));
       next_state.set(GameState::StartGame)
     }
   });
@@ -1,58 +1,231 @@
+use anyhow::Result;
 use bevy::prelude::*;
-use bevy_egui::{egui, EguiContexts};
+use bevy_egui::{
+  egui::{self, Color32},
+  EguiContexts,
+};
+use std::{
+  path::{Path, PathBuf},
+  time::Duration,
+};
 
-use crate::{game::GameState, storage::SystemInformation};
+use crate::{
+  game::{GameState, StartGameEvent},
+  storage::{
+    saves::{self, names, SaveSummary},
+    SystemInformation,
+  },
+};
+
+pub struct CharacterEntry {
+  // save id, the file stem
+  id: String,
+  summary: Option<SaveSummary>,
+}
+
+impl CharacterEntry {
+  fn display_name(&self) -> &str {
+    self
+      .summary
+      .as_ref()
+      .map_or(self.id.as_str(), |summary| summary.name.as_str())
+  }
+}
+
+// a management action awaiting input or confirmation
+enum CharacterAction {
+  Rename(String),
+  Duplicate(String),
+  Export(String),
+  Delete,
+}
 
 #[derive(Resource)]
 pub struct CharacterSelectionMenu {
-  characters: Vec<String>,
+  characters: Vec<CharacterEntry>,
+  // index into `characters` the action applies to
+  action: Option<(usize, CharacterAction)>,
+  import_path: String,
+  status: Option<String>,
 }
 
-pub fn on_enter(mut commands: Commands, sys_info: Res<SystemInformation>) {
-  let mut char_names = Vec::new();
-  if let Ok(dir) = sys_info.game_saves_path.read_dir() {
-    for file in dir {
-      if let Ok(file) = file {
-        if let Ok(kind) = file.file_type() {
-          if kind.is_file() {
-            if let Some(ext) = file.path().extension() {
-              if ext == "ms" {
-                if let Some(name) = file.file_name().to_str() {
-                  char_names.push(name.to_string());
-                }
-              }
-            }
-          }
-        }
-      }
+impl CharacterSelectionMenu {
+  fn new(dir: &Path) -> Self {
+    Self {
+      characters: scan(dir),
+      action: None,
+      import_path: String::new(),
+      status: None,
     }
   }
 
-  commands.insert_resource(CharacterSelectionMenu {
-    characters: char_names,
-  })
+  fn report(&mut self, dir: &Path, what: &str, result: Result<PathBuf>) {
+    self.status = Some(match result {
+      Ok(path) => format!("{} {}", what, path.display()),
+      Err(e) => format!("{} failed: {}", what, e),
+    });
+    self.action = None;
+    self.characters = scan(dir);
+  }
+}
+
+pub fn on_enter(mut commands: Commands, sys_info: Res<SystemInformation>) {
+  commands.insert_resource(CharacterSelectionMenu::new(&sys_info.game_saves_path))
 }
 
 pub fn on_update(
   mut next_state: ResMut<NextState<GameState>>,
+  mut event_writer: EventWriter<StartGameEvent>,
   mut contexts: EguiContexts,
-  menu: Res<CharacterSelectionMenu>,
+  mut menu: ResMut<CharacterSelectionMenu>,
+  sys_info: Res<SystemInformation>,
 ) {
+  let dir = sys_info.game_saves_path.as_path();
+
   egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
-    if ui.button("New Character").clicked() {
-      next_state.set(GameState::CharacterCreate);
+    ui.horizontal(|ui| {
+      if ui.button("New Character").clicked() {
+        next_state.set(GameState::CharacterCreate);
+      }
+
+      if ui.button("Back").clicked() {
+        next_state.set(GameState::MainMenu);
+      }
+    });
+
+    ui.horizontal(|ui| {
+      ui.text_edit_singleline(&mut menu.import_path);
+      if ui.button("Import").clicked() {
+        let result = saves::import(dir, Path::new(menu.import_path.trim()));
+        menu.report(dir, "Imported", result);
+      }
+    });
+
+    if let Some(status) = &menu.status {
+      ui.label(status.as_str());
+    }
+
+    if menu.characters.is_empty() {
+      return;
     }
 
-    if !menu.characters.is_empty() {
-      ui.separator();
+    ui.separator();
+
+    ui.label("Select Character");
+
+    // applied once the list is no longer borrowed
+    let mut outcome: Option<(&str, Result<PathBuf>)> = None;
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+      let CharacterSelectionMenu {
+        characters, action, ..
+      } = &mut *menu;
+
+      for (index, entry) in characters.iter().enumerate() {
+        ui.group(|ui| {
+          ui.horizontal(|ui| {
+            if ui.button(entry.display_name()).clicked() {
+              event_writer.send(StartGameEvent::Load(entry.id.clone()));
+              next_state.set(GameState::StartGame);
+            }
+
+            if ui.small_button("Rename").clicked() {
+              let new_name = entry.display_name().to_string();
+              *action = Some((index, CharacterAction::Rename(new_name)));
+            }
+
+            if ui.small_button("Duplicate").clicked() {
+              let new_name = format!("{} copy", entry.display_name());
+              *action = Some((index, CharacterAction::Duplicate(new_name)));
+            }
+
+            // no default, anywhere picked for the player could vanish or surprise them
+            if ui.small_button("Export").clicked() {
+              *action = Some((index, CharacterAction::Export(String::new())));
+            }
+
+            if ui.small_button("Delete").clicked() {
+              *action = Some((index, CharacterAction::Delete));
+            }
+          });
+
+          match &entry.summary {
+            Some(summary) => character_card(ui, summary),
+            None => {
+              ui.label("Save details unavailable");
+            }
+          }
+
+          let Some((_, pending)) = action.as_mut().filter(|(i, _)| *i == index) else {
+            return;
+          };
+
+          let mut cancelled = false;
+          let mut name_error = None;
+          // a rename may keep the current name, just differently cased
+          let own_name =
+            matches!(pending, CharacterAction::Rename(_)).then(|| entry.display_name());
+          ui.horizontal(|ui| {
+            let confirmed = match pending {
+              CharacterAction::Rename(input) | CharacterAction::Duplicate(input) => {
+                ui.text_edit_singleline(input);
+
+                let taken = characters
+                  .iter()
+                  .map(CharacterEntry::display_name)
+                  .filter(|other| Some(*other) != own_name);
+                name_error = names::validate(input.trim(), taken).err();
+
+                ui.add_enabled(name_error.is_none(), egui::Button::new("Confirm"))
+                  .clicked()
+              }
+              CharacterAction::Export(input) => {
+                ui.add(egui::TextEdit::singleline(input).hint_text("File or folder to export to"));
+                ui.add_enabled(!input.trim().is_empty(), egui::Button::new("Confirm"))
+                  .clicked()
+              }
+              CharacterAction::Delete => {
+                ui.label(format!("Delete {} permanently?", entry.display_name()));
+                ui.button("Delete").clicked()
+              }
+            };
+
+            if confirmed {
+              outcome = Some(match pending {
+                CharacterAction::Rename(new_name) => {
+                  ("Renamed to", saves::rename(dir, &entry.id, new_name.trim()))
+                }
+                CharacterAction::Duplicate(new_name) => (
+                  "Duplicated to",
+                  saves::duplicate(dir, &entry.id, new_name.trim()),
+                ),
+                CharacterAction::Export(dest) => (
+                  "Exported to",
+                  saves::export(dir, &entry.id, Path::new(dest.trim())),
+                ),
+                CharacterAction::Delete => {
+                  let file = saves::file_path(dir, &entry.id);
+                  ("Deleted", saves::delete(&file).map(|()| file))
+                }
+              });
+            }
 
-      ui.label("Select Character");
+            cancelled = ui.button("Cancel").clicked();
+          });
 
-      for char_name in &menu.characters {
-        if ui.button(char_name).clicked() {
-          next_state.set(GameState::StartGame);
-        }
+          if let Some(e) = name_error {
+            ui.colored_label(Color32::RED, e.to_string());
+          }
+
+          if cancelled {
+            *action = None;
+          }
+        });
       }
+    });
+
+    if let Some((what, result)) = outcome {
+      menu.report(dir, what, result);
     }
   });
 }
@@ -60,3 +233,62 @@ pub fn on_update(
 pub fn on_exit(mut commands: Commands) {
   commands.remove_resource::<CharacterSelectionMenu>();
 }
+
+fn scan(dir: &Path) -> Vec<CharacterEntry> {
+  let mut characters = saves::list(dir)
+    .into_iter()
+    .filter_map(|path| {
+      let id = path.file_stem()?.to_str()?.to_string();
+      let summary = SaveSummary::load(&path)
+        .map_err(|e| warn!("failed to read summary of {}: {}", path.display(), e))
+        .ok();
+      Some(CharacterEntry { id, summary })
+    })
+    .collect::<Vec<_>>();
+
+  // most recently played first
+  characters.sort_by_key(|entry| std::cmp::Reverse(entry.summary.as_ref().map(|s| s.last_played)));
+  characters
+}
+
+fn character_card(ui: &mut egui::Ui, summary: &SaveSummary) {
+  let attributes = &summary.attributes;
+  ui.label(format!(
+    "VIT {}  END {}  STR {}  DEX {}  AGI {}  INT {}  WIS {}  MND {}",
+    attributes.vitality,
+    attributes.endurance,
+    attributes.strength,
+    attributes.dexterity,
+    attributes.agility,
+    attributes.intelligence,
+    attributes.wisdom,
+    attributes.mind,
+  ));
+  ui.horizontal(|ui| {
+    ui.label(format!("Played {}", format_duration(summary.play_time)));
+    ui.separator();
+    ui.label(format!(
+      "Last played {}",
+      format_last_played(summary.last_played)
+    ));
+  });
+}
+
+fn format_duration(duration: Duration) -> String {
+  let minutes = duration.as_secs() / 60;
+  format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+pub fn format_last_played(last_played: u64) -> String {
+  if last_played == 0 {
+    return "unknown".to_string();
+  }
+
+  let elapsed = saves::unix_now().saturating_sub(last_played);
+  match elapsed {
+    0..=59 => "just now".to_string(),
+    60..=3599 => format!("{} minutes ago", elapsed / 60),
+    3600..=86399 => format!("{} hours ago", elapsed / 3600),
+    _ => format!("{} days ago", elapsed / 86400),
+  }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use bevy_egui::{
+  egui::{self, Align2, Color32, Frame},
+  EguiContexts,
+};
+
+use super::character_selection::format_last_played;
+use crate::{
+  game::{error::GameError, GameState, StartGameEvent},
+  storage::{
+    saves::{self, Backup},
+    SystemInformation,
+  },
+};
+
+// inserted alongside the transition to GameState::ErrorModal
+#[derive(Resource)]
+pub struct ErrorModal {
+  error: GameError,
+  // offered in place of the broken save, none when no backup loads either
+  backup: Option<Backup>,
+  status: Option<String>,
+  confirm_delete: bool,
+}
+
+impl ErrorModal {
+  pub fn new(error: GameError, backup: Option<Backup>) -> Self {
+    Self {
+      error,
+      backup,
+      status: None,
+      confirm_delete: false,
+    }
+  }
+}
+
+pub fn on_update(
+  mut next_state: ResMut<NextState<GameState>>,
+  mut event_writer: EventWriter<StartGameEvent>,
+  mut contexts: EguiContexts,
+  modal: Option<ResMut<ErrorModal>>,
+  sys_info: Res<SystemInformation>,
+) {
+  let Some(mut modal) = modal else {
+    next_state.set(GameState::CharacterSelect);
+    return;
+  };
+
+  let id = modal.error.save_id().map(str::to_string);
+
+  egui::CentralPanel::default()
+    .frame(Frame::default().fill(Color32::BLACK))
+    .show(contexts.ctx_mut(), |_ui| {});
+
+  egui::Window::new("Error")
+    .collapsible(false)
+    .resizable(false)
+    .anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+    .show(contexts.ctx_mut(), |ui| {
+      ui.label(modal.error.to_string());
+
+      if let Some(status) = &modal.status {
+        ui.colored_label(Color32::YELLOW, status);
+      }
+
+      ui.separator();
+
+      // nothing on disk to recover, all that's left is going back
+      let Some(id) = id else {
+        if ui.button("Back").clicked() {
+          next_state.set(GameState::CharacterSelect);
+        }
+        return;
+      };
+      let file_path = sys_info.save_path(&id);
+
+      if modal.confirm_delete {
+        ui.label(format!("Permanently delete save {}?", id));
+        ui.horizontal(|ui| {
+          if ui.button("Delete").clicked() {
+            match saves::delete(&file_path) {
+              Ok(()) => next_state.set(GameState::CharacterSelect),
+              Err(e) => modal.status = Some(format!("Failed to delete save: {}", e)),
+            }
+            modal.confirm_delete = false;
+          }
+
+          if ui.button("Cancel").clicked() {
+            modal.confirm_delete = false;
+          }
+        });
+        return;
+      }
+
+      ui.horizontal(|ui| {
+        if ui.button("Retry").clicked() {
+          event_writer.send(StartGameEvent::Load(id.clone()));
+          next_state.set(GameState::StartGame);
+        }
+
+        if let Some(backup) = &modal.backup {
+          let label = format!(
+            "Restore Backup ({})",
+            format_last_played(backup.last_played)
+          );
+          if ui.button(label).clicked() {
+            match saves::restore_backup(&file_path, backup) {
+              Ok(()) => {
+                event_writer.send(StartGameEvent::Load(id.clone()));
+                next_state.set(GameState::StartGame);
+              }
+              Err(e) => modal.status = Some(format!("Failed to restore backup: {}", e)),
+            }
+          }
+        }
+
+        if ui.button("Delete Save").clicked() {
+          modal.confirm_delete = true;
+        }
+
+        if ui.button("Back").clicked() {
+          next_state.set(GameState::CharacterSelect);
+        }
+      });
+    });
+}
+
+pub fn on_exit(mut commands: Commands) {
+  commands.remove_resource::<ErrorModal>();
+}
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use bevy_egui::{
+  egui::{self, Color32},
+  EguiContexts,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+  game::{
+    equipment::{BaseAttributes, Equipment},
+    input::ActionInput,
+    items::{Inventory, Item, Items},
+    stats::Stats,
+    Attributes, Overlay, PlayerCharacter,
+  },
+  storage::{
+    saves::{AttributeKind, Attributes as SavedAttributes, EquipSlot},
+    Action,
+  },
+};
+
+// a line of the screen that can be selected and acted on
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+  Slot(EquipSlot),
+  // index into the inventory's stacks
+  Stack(usize),
+}
+
+enum Navigation {
+  Up,
+  Down,
+  Activate,
+  Close,
+}
+
+#[derive(Default, Resource)]
+pub struct InventoryMenu {
+  selected: usize,
+  status: Option<String>,
+}
+
+pub fn on_enter(mut commands: Commands) {
+  commands.insert_resource(InventoryMenu::default());
+}
+
+pub fn on_update(
+  input: ActionInput,
+  mut contexts: EguiContexts,
+  mut next_overlay: ResMut<NextState<Overlay>>,
+  mut menu: ResMut<InventoryMenu>,
+  items: Res<Items>,
+  mut player: Query<
+    (
+      &BaseAttributes,
+      &Attributes,
+      &Stats,
+      &mut Equipment,
+      &mut Inventory,
+    ),
+    With<PlayerCharacter>,
+  >,
+) {
+  let Ok((base, attributes, stats, mut equipment, mut inventory)) = player.get_single_mut() else {
+    next_overlay.set(Overlay::None);
+    return;
+  };
+
+  let rows = EquipSlot::iter()
+    .map(Row::Slot)
+    .chain((0..inventory.stacks().len()).map(Row::Stack))
+    .collect::<Vec<_>>();
+  menu.selected = menu.selected.min(rows.len() - 1);
+
+  let mut activated = None;
+  let mut scroll_to_selected = false;
+  match navigation(&input) {
+    Some(Navigation::Up) => {
+      menu.selected = menu.selected.saturating_sub(1);
+      scroll_to_selected = true;
+    }
+    Some(Navigation::Down) => {
+      menu.selected = (menu.selected + 1).min(rows.len() - 1);
+      scroll_to_selected = true;
+    }
+    Some(Navigation::Activate) => activated = Some(rows[menu.selected]),
+    Some(Navigation::Close) => next_overlay.set(Overlay::None),
+    None => (),
+  }
+
+  egui::Window::new("Character")
+    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+    .collapsible(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      ui.columns(2, |columns| {
+        egui::ScrollArea::vertical()
+          .max_height(480.0)
+          .show(&mut columns[0], |ui| {
+            for (index, row) in rows.iter().enumerate() {
+              if matches!(row, Row::Stack(0)) {
+                ui.separator();
+                ui.heading("Inventory");
+              } else if index == 0 {
+                ui.heading("Equipment");
+              }
+
+              let (text, item, action) = match *row {
+                Row::Slot(slot) => {
+                  let item = equipment.get(slot).and_then(|id| items.get(id));
+                  let name = item.map_or("empty", |item| item.name.as_str());
+                  let action = item.map(|_| "Unequip");
+                  (format!("{}: {}", slot, name), item, action)
+                }
+                Row::Stack(stack) => {
+                  let stack = &inventory.stacks()[stack];
+                  let item = items.get(&stack.id);
+                  let name = item.map_or(stack.id.as_str(), |item| item.name.as_str());
+                  let text = if stack.count > 1 {
+                    format!("{} x{}", name, stack.count)
+                  } else {
+                    name.to_string()
+                  };
+                  let action = item.filter(|item| item.slot.is_some()).map(|_| "Equip");
+                  (text, item, action)
+                }
+              };
+
+              ui.horizontal(|ui| {
+                let mut response = ui.selectable_label(menu.selected == index, text);
+                if let Some(item) = item {
+                  response = response.on_hover_text(describe(item, &base.0));
+                }
+                if response.clicked() {
+                  menu.selected = index;
+                }
+                if scroll_to_selected && menu.selected == index {
+                  response.scroll_to_me(None);
+                }
+
+                if let Some(action) = action {
+                  if ui.small_button(action).clicked() {
+                    menu.selected = index;
+                    activated = Some(*row);
+                  }
+                }
+              });
+            }
+          });
+
+        let ui = &mut columns[1];
+        ui.heading("Attributes");
+        egui::Grid::new("character_sheet").show(ui, |ui| {
+          ui.label("");
+          ui.label("Base");
+          ui.label("Effective");
+          ui.end_row();
+          for kind in AttributeKind::iter() {
+            let (base, effective) = (base.0.get(kind), attributes.get(kind));
+            ui.label(kind.to_string());
+            ui.label(base.to_string());
+            match effective.cmp(&base) {
+              std::cmp::Ordering::Greater => {
+                ui.colored_label(Color32::GREEN, effective.to_string())
+              }
+              std::cmp::Ordering::Less => ui.colored_label(Color32::RED, effective.to_string()),
+              std::cmp::Ordering::Equal => ui.label(effective.to_string()),
+            };
+            ui.end_row();
+          }
+        });
+
+        ui.separator();
+
+        super::stats_grid(ui, stats);
+        ui.label(format!(
+          "Carrying {:.1} / {:.1}",
+          inventory.weight(&items) + equipment.weight(&items),
+          stats.carry_weight
+        ));
+      });
+
+      ui.separator();
+
+      // hover text is out of reach without a mouse
+      let selected_item = match rows[menu.selected] {
+        Row::Slot(slot) => equipment.get(slot),
+        Row::Stack(stack) => Some(inventory.stacks()[stack].id.as_str()),
+      }
+      .and_then(|id| items.get(id));
+      if let Some(item) = selected_item {
+        ui.label(describe(item, &base.0));
+      }
+
+      if let Some(status) = &menu.status {
+        ui.label(status.as_str());
+      }
+
+      if ui.button("Close").clicked() {
+        next_overlay.set(Overlay::None);
+      }
+    });
+
+  let Some(row) = activated else {
+    return;
+  };
+
+  let result = match row {
+    Row::Slot(slot) => equipment.unequip_to_inventory(slot, &mut inventory, &items),
+    Row::Stack(stack) => equipment
+      .equip_from_inventory(&mut inventory, stack, &items, &base.0)
+      .map(|_| ()),
+  };
+  menu.status = result.err().map(|e| format!("Cannot do that: {}", e));
+}
+
+pub fn on_exit(mut commands: Commands) {
+  commands.remove_resource::<InventoryMenu>();
+}
+
+fn navigation(input: &ActionInput) -> Option<Navigation> {
+  if input.just_pressed(Action::MenuUp) {
+    Some(Navigation::Up)
+  } else if input.just_pressed(Action::MenuDown) {
+    Some(Navigation::Down)
+  } else if input.just_pressed(Action::MenuConfirm) {
+    Some(Navigation::Activate)
+  } else if input.just_pressed(Action::Inventory) || input.just_pressed(Action::MenuBack) {
+    Some(Navigation::Close)
+  } else {
+    None
+  }
+}
+
+fn describe(item: &Item, base: &SavedAttributes) -> String {
+  let mut lines = vec![
+    item.description.clone(),
+    format!("Weight {:.1}", item.weight),
+  ];
+
+  for kind in AttributeKind::iter() {
+    if let Some(amount) = item.modifiers.get(&kind) {
+      lines.push(format!("{:+} {}", amount, kind));
+    }
+  }
+
+  for kind in AttributeKind::iter() {
+    if let Some(value) = item.requirements.get(&kind) {
+      let unmet = if base.get(kind) < *value {
+        " (unmet)"
+      } else {
+        ""
+      };
+      lines.push(format!("Requires {} {}{}", value, kind, unmet));
+    }
+  }
+
+  lines.join("\n")
+}
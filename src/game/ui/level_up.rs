@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use strum::IntoEnumIterator;
+
+use crate::{
+  game::{
+    equipment::{self, BaseAttributes, Buffs, Equipment},
+    input::ActionInput,
+    items::Items,
+    progression::{Progression, ProgressionConfig},
+    stats::{StatCurves, Stats},
+    Overlay, PlayerCharacter,
+  },
+  storage::{
+    saves::{AttributeKind, Attributes as SavedAttributes},
+    Action,
+  },
+};
+
+#[derive(Resource)]
+pub struct LevelUpMenu {
+  // the character's attributes before any pending points
+  base: SavedAttributes,
+  pending: SavedAttributes,
+}
+
+impl LevelUpMenu {
+  fn allocated(&self) -> u32 {
+    AttributeKind::iter()
+      .map(|kind| self.pending.get(kind) - self.base.get(kind))
+      .sum()
+  }
+}
+
+pub fn on_enter(mut commands: Commands, player: Query<&BaseAttributes, With<PlayerCharacter>>) {
+  let base = player
+    .get_single()
+    .map(|base| base.0.clone())
+    .unwrap_or_default();
+  commands.insert_resource(LevelUpMenu {
+    pending: base.clone(),
+    base,
+  });
+}
+
+// the key that opened the menu closes it again
+pub fn close_input(input: ActionInput, mut next_overlay: ResMut<NextState<Overlay>>) {
+  if input.just_pressed(Action::LevelUp) {
+    next_overlay.set(Overlay::None);
+  }
+}
+
+type LevelingPlayer<'w, 's> = Query<
+  'w,
+  's,
+  (
+    &'static mut BaseAttributes,
+    &'static Equipment,
+    &'static Buffs,
+    &'static mut Progression,
+  ),
+  With<PlayerCharacter>,
+>;
+
+pub fn on_update(
+  mut contexts: EguiContexts,
+  mut next_overlay: ResMut<NextState<Overlay>>,
+  mut menu: ResMut<LevelUpMenu>,
+  config: Res<ProgressionConfig>,
+  curves: Res<StatCurves>,
+  items: Res<Items>,
+  mut player: LevelingPlayer,
+) {
+  let Ok((mut base, equipment, buffs, mut progression)) = player.get_single_mut() else {
+    next_overlay.set(Overlay::None);
+    return;
+  };
+
+  egui::Window::new("Level Up")
+    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+    .collapsible(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      ui.label(format!("Level {}", progression.level()));
+      let remaining = progression.unspent_points() - menu.allocated();
+      ui.label(format!("Points remaining: {}", remaining));
+
+      ui.separator();
+
+      egui::Grid::new("attributes").show(ui, |ui| {
+        for kind in AttributeKind::iter() {
+          let value = menu.pending.get(kind);
+          ui.label(kind.to_string());
+          if ui
+            .add_enabled(value > menu.base.get(kind), egui::Button::new("-"))
+            .clicked()
+          {
+            *menu.pending.get_mut(kind) -= 1;
+          }
+          ui.label(format!("{} / {}", value, config.max_attribute));
+          if ui
+            .add_enabled(
+              remaining > 0 && value < config.max_attribute,
+              egui::Button::new("+"),
+            )
+            .clicked()
+          {
+            *menu.pending.get_mut(kind) += 1;
+          }
+          ui.end_row();
+        }
+      });
+
+      ui.separator();
+
+      let modifiers = equipment.modifiers(&items).chain(buffs.modifiers());
+      let preview = Stats::derive(&equipment::effective(&menu.pending, modifiers), &curves);
+      super::stats_grid(ui, &preview);
+
+      ui.separator();
+
+      ui.horizontal(|ui| {
+        let allocated = menu.allocated();
+        if ui
+          .add_enabled(allocated > 0, egui::Button::new("Confirm"))
+          .clicked()
+          && progression.spend(allocated)
+        {
+          // effective attributes, stats and vitals follow through change detection
+          base.0 = menu.pending.clone();
+          menu.base = menu.pending.clone();
+        }
+
+        if ui.button("Close").clicked() {
+          next_overlay.set(Overlay::None);
+        }
+      });
+    });
+}
+
+pub fn on_exit(mut commands: Commands) {
+  commands.remove_resource::<LevelUpMenu>();
+}
@@ -168,14 +168,21 @@ pub fn on_enter(mut commands: Commands, _asset_server: Res<AssetServer>) {
   commands.insert_resource(menu);
 }
 
+type ButtonInteractions<'w, 's> = Query<
+  'w,
+  's,
+  (&'static Interaction, &'static MainMenuButton),
+  (Changed<Interaction>, With<Button>),
+>;
+
 pub fn on_update(
   mut next_state: ResMut<NextState<GameState>>,
   mut exit: EventWriter<AppExit>,
-  interaction_query: Query<(&Interaction, &MainMenuButton), (Changed<Interaction>, With<Button>)>,
+  interaction_query: ButtonInteractions,
 ) {
   for (interaction, button) in interaction_query.into_iter() {
-    match interaction {
-      Interaction::Pressed => match button.kind() {
+    if *interaction == Interaction::Pressed {
+      match button.kind() {
         MainMenuButtonType::Play => {
           next_state.set(GameState::CharacterSelect);
         }
@@ -185,8 +192,7 @@ pub fn on_update(
         MainMenuButtonType::Exit => {
           exit.send(AppExit);
         }
-      },
-      _ => (),
+      }
     }
   }
 }
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use super::settings_menu::SettingsReturn;
+use crate::game::{persistence::SaveGameEvent, GameState, Overlay, QuitEvent};
+
+#[derive(Default, Resource)]
+pub struct PauseMenu {
+  // quitting is a two step affair so a stray click doesn't end the session
+  confirm_quit: Option<QuitEvent>,
+}
+
+pub fn on_enter(mut commands: Commands) {
+  commands.insert_resource(PauseMenu::default());
+}
+
+pub fn on_update(
+  mut commands: Commands,
+  mut contexts: EguiContexts,
+  mut menu: ResMut<PauseMenu>,
+  mut next_state: ResMut<NextState<GameState>>,
+  mut next_overlay: ResMut<NextState<Overlay>>,
+  mut save_writer: EventWriter<SaveGameEvent>,
+  mut quit_writer: EventWriter<QuitEvent>,
+) {
+  egui::Window::new("Paused")
+    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+    .collapsible(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      if let Some(quit) = menu.confirm_quit {
+        ui.label(match quit {
+          QuitEvent::ToMainMenu => "Save and return to the main menu?",
+          QuitEvent::ToDesktop => "Save and quit to desktop?",
+        });
+        ui.horizontal(|ui| {
+          if ui.button("Quit").clicked() {
+            quit_writer.send(quit);
+          }
+          if ui.button("Cancel").clicked() {
+            menu.confirm_quit = None;
+          }
+        });
+        return;
+      }
+
+      ui.vertical_centered_justified(|ui| {
+        if ui.button("Resume").clicked() {
+          next_overlay.set(Overlay::None);
+        }
+
+        if ui.button("Settings").clicked() {
+          // the session and this overlay stay put until settings hands back
+          commands.insert_resource(SettingsReturn(GameState::Gameplay));
+          next_state.set(GameState::SettingsMenu);
+        }
+
+        if ui.button("Save").clicked() {
+          save_writer.send(SaveGameEvent);
+        }
+
+        ui.separator();
+
+        if ui.button("Quit to Main Menu").clicked() {
+          menu.confirm_quit = Some(QuitEvent::ToMainMenu);
+        }
+
+        if ui.button("Quit to Desktop").clicked() {
+          menu.confirm_quit = Some(QuitEvent::ToDesktop);
+        }
+      });
+    });
+}
+
+pub fn on_exit(mut commands: Commands) {
+  commands.remove_resource::<PauseMenu>();
+}
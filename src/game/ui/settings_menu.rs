@@ -1,30 +1,122 @@
-use super::WindowEvent;
+use super::ApplySettingsEvent;
 use crate::{
-  game::GameState,
-  storage::{Settings, SystemInformation, WindowMode},
+  game::{display::Monitors, GameState},
+  storage::{
+    Action, Binding, Device, GraphicsSettings, InputSettings, LookSettings, PresentMode,
+    ResponseCurve, Settings, StickSettings, SystemInformation, WindowMode,
+  },
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_egui::{
   egui::{self, Color32, Frame, Ui},
   EguiContexts,
 };
-use phf::{phf_map, Map};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
 use strum::IntoEnumIterator;
 
-static RESOLUTIONS: Map<&'static str, UVec2> = phf_map! {
-  "1280x720" => UVec2::new(1280, 720),
-  "1920x1080" => UVec2::new(1920, 1080),
-};
+// offered when the monitors couldn't be read
+const FALLBACK_RESOLUTIONS: [(u32, u32); 2] = [(1920, 1080), (1280, 720)];
+
+// how long applied display changes wait on the player before going back
+const CONFIRM_SECONDS: u64 = 15;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Page {
+  #[default]
+  General,
+  Graphics,
+  Controls,
+}
+
+// waiting for the next press to bind to `action`
+#[derive(Clone, Copy)]
+struct Rebind {
+  action: Action,
+  device: Device,
+}
+
+// a press that is already taken, waiting on the player to decide
+#[derive(Clone, Copy)]
+struct Conflict {
+  action: Action,
+  binding: Binding,
+  other: Action,
+}
 
-#[derive(Default, Resource)]
+// applied display changes that go back unless kept in time
+struct Confirmation {
+  previous: Settings,
+  timer: Timer,
+}
+
+// draws one row of the general page
+type MenuEntry = Box<dyn FnMut(&mut Ui, &mut Settings, &Monitors) + Send + Sync>;
+
+#[derive(Resource)]
 pub struct SettingsMenu {
-  menu_map: BTreeMap<
-    &'static str,
-    Box<dyn FnMut(&mut Ui, &mut Settings, &mut EventWriter<WindowEvent>) + Send + Sync>,
-  >,
+  menu_map: BTreeMap<&'static str, MenuEntry>,
+  page: Page,
+  rebind: Option<Rebind>,
+  conflict: Option<Conflict>,
+  // what the menu edits, the live settings only change on Apply
+  pending: Settings,
+  confirmation: Option<Confirmation>,
+}
+
+impl SettingsMenu {
+  fn new(settings: &Settings) -> Self {
+    Self {
+      menu_map: BTreeMap::new(),
+      page: Page::default(),
+      rebind: None,
+      conflict: None,
+      pending: settings.clone(),
+      confirmation: None,
+    }
+  }
+
+  // commits the pending settings, holding on to the old ones if the display changed
+  fn apply(&mut self, settings: &mut Settings, monitors: &Monitors) {
+    monitors.validate(&mut self.pending.window);
+    let previous = std::mem::replace(settings, self.pending.clone());
+    if previous.window != self.pending.window || previous.graphics != self.pending.graphics {
+      self.confirmation = Some(Confirmation {
+        previous,
+        timer: Timer::new(Duration::from_secs(CONFIRM_SECONDS), TimerMode::Once),
+      });
+    }
+  }
+
+  // puts back what was live before the last Apply
+  fn revert(&mut self, settings: &mut Settings) {
+    if let Some(confirmation) = self.confirmation.take() {
+      self.pending = confirmation.previous.clone();
+      *settings = confirmation.previous;
+    }
+  }
+
+  fn capture(&mut self, binding: Binding) {
+    let Some(rebind) = self.rebind.take() else {
+      return;
+    };
+    let input = &mut self.pending.input;
+    match input.conflict(rebind.action, binding) {
+      Some(other) => {
+        self.conflict = Some(Conflict {
+          action: rebind.action,
+          binding,
+          other,
+        })
+      }
+      None => input.bind(rebind.action, rebind.device, Some(binding)),
+    }
+  }
 }
 
+// where Back leads, the main menu unless someone said otherwise
+#[derive(Resource)]
+pub struct SettingsReturn(pub GameState);
+
 #[derive(Event)]
 pub struct SaveSettingsEvent;
 
@@ -41,64 +133,265 @@ impl SaveSettingsEvent {
   }
 }
 
-pub fn on_enter(mut commands: Commands) {
-  let mut menu = SettingsMenu::default();
+pub fn on_enter(mut commands: Commands, sys_info: Res<SystemInformation>) {
+  let mut menu = SettingsMenu::new(&sys_info.settings);
 
+  menu.menu_map.insert(
+    "Monitor",
+    Box::new(|ui, settings, monitors| {
+      let current = monitors
+        .get(settings.window.monitor.as_deref())
+        .map(|monitor| monitor.name.clone())
+        .unwrap_or_else(|| "Primary".to_string());
+      ui.collapsing(current, |ui| {
+        for monitor in monitors.iter() {
+          let label = if monitor.primary {
+            format!("{} (primary)", monitor.name)
+          } else {
+            monitor.name.clone()
+          };
+          if ui.button(label).clicked() {
+            settings.window.monitor = Some(monitor.name.clone());
+            monitors.validate(&mut settings.window);
+          }
+        }
+      });
+    }),
+  );
   menu.menu_map.insert(
     "Window Size",
-    Box::new(|ui, settings, window_event_writer| {
+    Box::new(|ui, settings, monitors| {
+      let resolutions = monitors
+        .get(settings.window.monitor.as_deref())
+        .map(|monitor| monitor.resolutions())
+        .filter(|resolutions| !resolutions.is_empty())
+        .unwrap_or_else(|| FALLBACK_RESOLUTIONS.to_vec());
       ui.collapsing(
         format!("{}x{}", settings.window.width, settings.window.height),
         |ui| {
-          for (display, res) in RESOLUTIONS.entries() {
-            if ui.button(*display).clicked() {
-              window_event_writer.send(WindowEvent::Resize(res.x, res.y));
+          for (width, height) in resolutions {
+            if ui.button(format!("{}x{}", width, height)).clicked() {
+              settings.window.width = width;
+              settings.window.height = height;
+              // a refresh rate picked for the old size may not exist at the new one
+              monitors.validate(&mut settings.window);
             }
           }
         },
       );
     }),
   );
+  menu.menu_map.insert(
+    "Refresh Rate",
+    Box::new(|ui, settings, monitors| {
+      let window = &mut settings.window;
+      let refresh_rates = monitors
+        .get(window.monitor.as_deref())
+        .map(|monitor| monitor.refresh_rates(window.width, window.height))
+        .unwrap_or_default();
+      let hertz = |refresh_rate: u32| format!("{:.2} Hz", refresh_rate as f32 / 1000.0);
+      // only exclusive fullscreen gets to pick
+      ui.add_enabled_ui(matches!(window.mode, WindowMode::Fullscreen), |ui| {
+        ui.collapsing(
+          window
+            .refresh_rate
+            .map(hertz)
+            .unwrap_or_else(|| "Highest".to_string()),
+          |ui| {
+            if ui.button("Highest").clicked() {
+              window.refresh_rate = None;
+            }
+            for refresh_rate in refresh_rates {
+              if ui.button(hertz(refresh_rate)).clicked() {
+                window.refresh_rate = Some(refresh_rate);
+              }
+            }
+          },
+        );
+      });
+    }),
+  );
   menu.menu_map.insert(
     "Window Mode",
-    Box::new(|ui, settings, window_event_writer| {
+    Box::new(|ui, settings, monitors| {
       ui.collapsing(settings.window.mode.to_string(), |ui| {
         for mode in WindowMode::iter() {
           if ui.button(mode.to_string()).clicked() {
-            window_event_writer.send(WindowEvent::ModeChange(mode));
+            settings.window.mode = mode;
+            monitors.validate(&mut settings.window);
           }
         }
       });
     }),
   );
+  menu.menu_map.insert(
+    "Autosave Interval",
+    Box::new(|ui, settings, _| {
+      ui.add(
+        egui::DragValue::new(&mut settings.saves.autosave_interval)
+          .clamp_range(0..=3600)
+          .suffix(" s"),
+      );
+    }),
+  );
   commands.insert_resource(menu);
 }
 
+// goes back to the previous settings once the confirmation runs out
+pub fn tick_confirmation(
+  time: Res<Time>,
+  mut sys_info: ResMut<SystemInformation>,
+  mut settings_menu: ResMut<SettingsMenu>,
+  mut apply_settings_event_writer: EventWriter<ApplySettingsEvent>,
+) {
+  if let Some(confirmation) = &mut settings_menu.confirmation {
+    if confirmation.timer.tick(time.delta()).just_finished() {
+      settings_menu.revert(&mut sys_info.settings);
+      apply_settings_event_writer.send(ApplySettingsEvent);
+    }
+  }
+}
+
+// runs before the menu draws, so the click that starts a rebind isn't taken as the binding
+pub fn capture_rebind(
+  mut settings_menu: ResMut<SettingsMenu>,
+  kbd: Res<Input<KeyCode>>,
+  mouse_buttons: Res<Input<MouseButton>>,
+  gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+  if let Some(rebind) = settings_menu.rebind {
+    // escape and start always back out, the old binding stays
+    let cancelled = kbd.just_pressed(KeyCode::Escape)
+      || gamepad_buttons
+        .get_just_pressed()
+        .any(|button| button.button_type == GamepadButtonType::Start);
+    let pressed = match rebind.device {
+      Device::Keyboard => kbd
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+          mouse_buttons
+            .get_just_pressed()
+            .next()
+            .map(|button| Binding::Mouse(*button))
+        }),
+      Device::Gamepad => gamepad_buttons
+        .get_just_pressed()
+        .next()
+        .map(|button| Binding::Gamepad(button.button_type)),
+    };
+    if cancelled {
+      settings_menu.rebind = None;
+    } else if let Some(binding) = pressed {
+      settings_menu.capture(binding);
+    }
+  }
+}
+
+#[derive(SystemParam)]
+pub struct SettingsEvents<'w> {
+  apply: EventWriter<'w, ApplySettingsEvent>,
+  save: EventWriter<'w, SaveSettingsEvent>,
+}
+
 pub fn on_update(
   mut next_state: ResMut<NextState<GameState>>,
   mut sys_info: ResMut<SystemInformation>,
   mut contexts: EguiContexts,
   mut settings_menu: ResMut<SettingsMenu>,
-  mut window_resize_event_writer: EventWriter<WindowEvent>,
-  mut save_settings_event_writer: EventWriter<SaveSettingsEvent>,
+  monitors: Res<Monitors>,
+  settings_return: Option<Res<SettingsReturn>>,
+  mut events: SettingsEvents,
 ) {
+  let settings_menu = &mut *settings_menu;
+
   egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
     ui.heading("Settings");
 
-    for (key, value) in settings_menu.menu_map.iter_mut() {
+    // a display that came out unusable can't be clicked through, so the countdown decides
+    if let Some(confirmation) = &settings_menu.confirmation {
+      let remaining = confirmation.timer.remaining_secs().ceil();
+      ui.label("Keep these settings?");
+      ui.label(format!("Reverting in {} s", remaining));
       ui.horizontal(|ui| {
-        ui.label(key.to_string());
-        value(ui, &mut sys_info.settings, &mut window_resize_event_writer);
+        if ui.button("Keep").clicked() {
+          settings_menu.confirmation = None;
+        }
+        if ui.button("Revert").clicked() {
+          settings_menu.revert(&mut sys_info.settings);
+          events.apply.send(ApplySettingsEvent);
+        }
       });
+      return;
+    }
+
+    // while listening any click doubles as the new binding, so offer nothing to click
+    if settings_menu.rebind.is_some() {
+      controls(ui, settings_menu);
+      return;
     }
 
     ui.horizontal(|ui| {
+      for (page, label) in [
+        (Page::General, "General"),
+        (Page::Graphics, "Graphics"),
+        (Page::Controls, "Controls"),
+      ] {
+        let selected = settings_menu.page == page;
+        if ui.selectable_label(selected, label).clicked() {
+          settings_menu.page = page;
+        }
+      }
+    });
+
+    ui.separator();
+
+    match settings_menu.page {
+      Page::General => {
+        let SettingsMenu {
+          menu_map, pending, ..
+        } = settings_menu;
+        for (key, value) in menu_map.iter_mut() {
+          ui.horizontal(|ui| {
+            ui.label(key.to_string());
+            value(ui, pending, &monitors);
+          });
+        }
+      }
+      Page::Graphics => graphics(ui, &mut settings_menu.pending.graphics),
+      Page::Controls => controls(ui, settings_menu),
+    }
+
+    ui.separator();
+
+    let unapplied = settings_menu.pending != sys_info.settings;
+    ui.horizontal(|ui| {
+      // leaving drops whatever wasn't applied
       if ui.button("Back").clicked() {
-        next_state.set(GameState::MainMenu);
+        next_state.set(
+          settings_return
+            .as_ref()
+            .map(|back| back.0)
+            .unwrap_or(GameState::MainMenu),
+        );
+      }
+
+      if ui
+        .add_enabled(unapplied, egui::Button::new("Apply"))
+        .clicked()
+      {
+        settings_menu.apply(&mut sys_info.settings, &monitors);
+        events.apply.send(ApplySettingsEvent);
       }
 
-      if ui.button("Save").clicked() {
-        save_settings_event_writer.send(SaveSettingsEvent);
+      // only applied settings are saved
+      if ui
+        .add_enabled(!unapplied, egui::Button::new("Save"))
+        .on_disabled_hover_text("Apply the changes first")
+        .clicked()
+      {
+        events.save.send(SaveSettingsEvent);
       }
     });
   });
@@ -107,6 +400,192 @@ pub fn on_update(
     .show(contexts.ctx_mut(), |_ui| {});
 }
 
-pub fn on_exit(mut commands: Commands) {
+fn graphics(ui: &mut Ui, graphics: &mut GraphicsSettings) {
+  egui::Grid::new("graphics").show(ui, |ui| {
+    ui.label("Present Mode");
+    ui.horizontal(|ui| {
+      for mode in PresentMode::iter() {
+        ui.selectable_value(&mut graphics.present_mode, mode, mode.to_string());
+      }
+    });
+    ui.end_row();
+
+    ui.label("Frame Cap");
+    let frame_cap_text = |frame_cap: u32| match frame_cap {
+      0 => "Uncapped".to_string(),
+      frame_cap => format!("{} fps", frame_cap),
+    };
+    egui::ComboBox::from_id_source("frame_cap")
+      .selected_text(frame_cap_text(graphics.frame_cap))
+      .show_ui(ui, |ui| {
+        for frame_cap in GraphicsSettings::FRAME_CAPS {
+          ui.selectable_value(
+            &mut graphics.frame_cap,
+            frame_cap,
+            frame_cap_text(frame_cap),
+          );
+        }
+      });
+    ui.end_row();
+
+    ui.label("Anti-aliasing");
+    ui.horizontal(|ui| {
+      for samples in GraphicsSettings::MSAA_SAMPLES {
+        let label = match samples {
+          1 => "Off".to_string(),
+          samples => format!("{}x MSAA", samples),
+        };
+        ui.selectable_value(&mut graphics.msaa_samples, samples, label);
+      }
+    });
+    ui.end_row();
+
+    ui.label("Shadows");
+    ui.horizontal(|ui| {
+      ui.checkbox(&mut graphics.shadows, "Enabled");
+      ui.add_enabled(
+        graphics.shadows,
+        egui::Checkbox::new(&mut graphics.high_res_shadows, "High Resolution"),
+      );
+    });
+    ui.end_row();
+
+    ui.label("Render Scale");
+    let slider = egui::Slider::new(
+      &mut graphics.render_scale,
+      GraphicsSettings::MIN_RENDER_SCALE..=GraphicsSettings::MAX_RENDER_SCALE,
+    )
+    .step_by(0.05)
+    .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0));
+    ui.add(slider);
+    ui.end_row();
+  });
+}
+
+fn controls(ui: &mut Ui, menu: &mut SettingsMenu) {
+  let input = &mut menu.pending.input;
+  if let Some(conflict) = menu.conflict {
+    ui.colored_label(
+      Color32::YELLOW,
+      format!(
+        "{} is already bound to {}. Bind it to {} instead?",
+        conflict.binding, conflict.other, conflict.action
+      ),
+    );
+    ui.horizontal(|ui| {
+      if ui.button("Replace").clicked() {
+        let device = conflict.binding.device();
+        input.bind(conflict.other, device, None);
+        input.bind(conflict.action, device, Some(conflict.binding));
+        menu.conflict = None;
+      }
+      if ui.button("Cancel").clicked() {
+        menu.conflict = None;
+      }
+    });
+    return;
+  }
+
+  if let Some(rebind) = menu.rebind {
+    ui.label(format!(
+      "Press a {} for {}, or Escape / Start to cancel",
+      match rebind.device {
+        Device::Keyboard => "key or mouse button",
+        Device::Gamepad => "gamepad button",
+      },
+      rebind.action
+    ));
+    return;
+  }
+
+  egui::Grid::new("controls").striped(true).show(ui, |ui| {
+    ui.label("Action");
+    for device in Device::iter() {
+      ui.label(device.to_string());
+    }
+    ui.end_row();
+
+    for action in Action::iter() {
+      ui.label(action.to_string());
+      for device in Device::iter() {
+        let label = input
+          .binding(action, device)
+          .map(|binding| binding.to_string())
+          .unwrap_or_else(|| "-".to_string());
+        let button = ui
+          .button(label)
+          .on_hover_text("Click to rebind, right click to clear");
+        if button.clicked() {
+          menu.rebind = Some(Rebind { action, device });
+        } else if button.secondary_clicked() {
+          input.bind(action, device, None);
+        }
+      }
+      ui.end_row();
+    }
+  });
+
+  ui.separator();
+
+  egui::Grid::new("look").show(ui, |ui| {
+    look_row(
+      ui,
+      "Mouse",
+      &mut input.mouse_look,
+      LookSettings::MOUSE_SENSITIVITY,
+    );
+    look_row(
+      ui,
+      "Gamepad",
+      &mut input.gamepad_look,
+      LookSettings::GAMEPAD_SENSITIVITY,
+    );
+
+    let sticks = &mut input.sticks;
+    ui.label("Stick Deadzone");
+    ui.add(
+      egui::Slider::new(&mut sticks.inner_deadzone, StickSettings::INNER_DEADZONE).text("inner"),
+    );
+    ui.add(
+      egui::Slider::new(&mut sticks.outer_deadzone, StickSettings::OUTER_DEADZONE).text("outer"),
+    );
+    ui.end_row();
+
+    ui.label("Stick Response");
+    ui.horizontal(|ui| {
+      for curve in ResponseCurve::iter() {
+        ui.selectable_value(&mut sticks.curve, curve, curve.to_string());
+      }
+    });
+    ui.end_row();
+  });
+
+  if ui.button("Reset to Defaults").clicked() {
+    *input = InputSettings::default();
+  }
+}
+
+fn look_row(ui: &mut Ui, label: &str, look: &mut LookSettings, range: RangeInclusive<f32>) {
+  ui.label(format!("{} Sensitivity", label));
+  ui.add(egui::Slider::new(&mut look.sensitivity, range));
+  ui.horizontal(|ui| {
+    ui.checkbox(&mut look.invert_x, "Invert X");
+    ui.checkbox(&mut look.invert_y, "Invert Y");
+  });
+  ui.end_row();
+}
+
+// display changes left waiting on confirmation don't outlive the menu
+pub fn on_exit(
+  mut commands: Commands,
+  mut sys_info: ResMut<SystemInformation>,
+  mut settings_menu: ResMut<SettingsMenu>,
+  mut apply_settings_event_writer: EventWriter<ApplySettingsEvent>,
+) {
+  if settings_menu.confirmation.is_some() {
+    settings_menu.revert(&mut sys_info.settings);
+    apply_settings_event_writer.send(ApplySettingsEvent);
+  }
   commands.remove_resource::<SettingsMenu>();
+  commands.remove_resource::<SettingsReturn>();
 }
@@ -10,9 +10,6 @@ pub struct UiPlayground {
 #[derive(Component)]
 pub struct UiPlaygroundButton;
 
-#[derive(Component)]
-pub struct UiPlaygroundText;
-
 pub fn on_enter(
   mut commands: Commands,
   entities: Query<Entity>,
@@ -59,14 +56,14 @@ pub fn on_enter(
   commands.insert_resource(playground);
 }
 
-pub fn on_update(
-  _playground: Res<UiPlayground>,
-  _interaction_query: Query<
-    (&Interaction, &UiPlaygroundButton),
-    (Changed<Interaction>, With<Button>),
-  >,
-) {
-}
+type ButtonInteractions<'w, 's> = Query<
+  'w,
+  's,
+  (&'static Interaction, &'static UiPlaygroundButton),
+  (Changed<Interaction>, With<Button>),
+>;
+
+pub fn on_update(_playground: Res<UiPlayground>, _interaction_query: ButtonInteractions) {}
 
 pub fn on_exit(mut commands: Commands, playground: Res<UiPlayground>) {
   commands.entity(playground.handle).despawn();
@@ -0,0 +1,241 @@
+use super::{data, stats::Stats};
+use crate::storage::saves::VitalsState;
+use anyhow::Result;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Resource, Deserialize)]
+pub struct VitalsConfig {
+  // per second, as a fraction of the maximum
+  pub health_regen: f32,
+  pub stamina_regen: f32,
+  pub mana_regen: f32,
+  // seconds after a vital was spent before it regenerates
+  pub regen_delay: f32,
+  // stamina per second
+  pub sprint_cost: f32,
+  pub sprint_multiplier: f32,
+}
+
+impl VitalsConfig {
+  const FILE: &'static str = "vitals.toml";
+
+  pub fn load() -> Result<Self> {
+    data::load(Self::FILE)
+  }
+}
+
+// a depletable resource bounded by a maximum derived from stats
+#[derive(Debug, Clone, Copy)]
+pub struct Pool {
+  current: f32,
+  max: f32,
+  // seconds since the pool was last spent from
+  idle: f32,
+}
+
+impl Pool {
+  pub fn new(current: f32, max: f32) -> Self {
+    let max = max.max(0.0);
+    Self {
+      current: current.clamp(0.0, max),
+      max,
+      idle: 0.0,
+    }
+  }
+
+  pub fn current(&self) -> f32 {
+    self.current
+  }
+
+  pub fn max(&self) -> f32 {
+    self.max
+  }
+
+  pub fn fraction(&self) -> f32 {
+    if self.max > 0.0 {
+      self.current / self.max
+    } else {
+      0.0
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.current <= 0.0
+  }
+
+  // spends `amount` only if all of it is available
+  pub fn try_spend(&mut self, amount: f32) -> bool {
+    if self.current < amount {
+      return false;
+    }
+    self.current -= amount;
+    self.idle = 0.0;
+    true
+  }
+
+  // spends up to `amount`, stopping at zero
+  pub fn drain(&mut self, amount: f32) {
+    self.current = (self.current - amount).max(0.0);
+    self.idle = 0.0;
+  }
+
+  pub fn restore(&mut self, amount: f32) {
+    self.current = (self.current + amount).min(self.max);
+  }
+
+  pub fn set_max(&mut self, max: f32) {
+    self.max = max.max(0.0);
+    self.current = self.current.min(self.max);
+  }
+
+  fn regenerate(&mut self, rate: f32, delay: f32, delta: f32) {
+    self.idle += delta;
+    if self.idle >= delay && self.current < self.max {
+      self.restore(self.max * rate * delta);
+    }
+  }
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Health(pub Pool);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Stamina(pub Pool);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Mana(pub Pool);
+
+#[derive(Bundle)]
+pub struct VitalsBundle {
+  health: Health,
+  stamina: Stamina,
+  mana: Mana,
+}
+
+impl VitalsBundle {
+  // restores saved values, or starts full when there are none
+  pub fn new(stats: &Stats, saved: Option<VitalsState>) -> Self {
+    let saved = saved.unwrap_or(VitalsState {
+      health: stats.max_health,
+      stamina: stats.max_stamina,
+      mana: stats.max_mana,
+    });
+
+    Self {
+      health: Health(Pool::new(saved.health, stats.max_health)),
+      stamina: Stamina(Pool::new(saved.stamina, stats.max_stamina)),
+      mana: Mana(Pool::new(saved.mana, stats.max_mana)),
+    }
+  }
+}
+
+pub fn sync_maxima(
+  mut query: Query<(&Stats, &mut Health, &mut Stamina, &mut Mana), Changed<Stats>>,
+) {
+  for (stats, mut health, mut stamina, mut mana) in query.iter_mut() {
+    health.set_max(stats.max_health);
+    stamina.set_max(stats.max_stamina);
+    mana.set_max(stats.max_mana);
+  }
+}
+
+pub fn regenerate(
+  time: Res<Time>,
+  config: Res<VitalsConfig>,
+  mut query: Query<(&mut Health, &mut Stamina, &mut Mana)>,
+) {
+  let delta = time.delta_seconds();
+  for (mut health, mut stamina, mut mana) in query.iter_mut() {
+    health.regenerate(config.health_regen, config.regen_delay, delta);
+    stamina.regenerate(config.stamina_regen, config.regen_delay, delta);
+    mana.regenerate(config.mana_regen, config.regen_delay, delta);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn new_clamps_into_range() {
+    assert_eq!(Pool::new(150.0, 100.0).current(), 100.0);
+    assert_eq!(Pool::new(-5.0, 100.0).current(), 0.0);
+    let pool = Pool::new(10.0, -20.0);
+    assert_eq!((pool.current(), pool.max()), (0.0, 0.0));
+  }
+
+  #[test]
+  fn fraction_of_an_empty_maximum_is_zero() {
+    assert_eq!(Pool::new(50.0, 200.0).fraction(), 0.25);
+    assert_eq!(Pool::new(0.0, 0.0).fraction(), 0.0);
+  }
+
+  #[test]
+  fn try_spend_is_all_or_nothing() {
+    let mut pool = Pool::new(30.0, 100.0);
+    assert!(!pool.try_spend(31.0));
+    assert_eq!(pool.current(), 30.0);
+    assert!(pool.try_spend(30.0));
+    assert!(pool.is_empty());
+  }
+
+  #[test]
+  fn drain_stops_at_zero() {
+    let mut pool = Pool::new(30.0, 100.0);
+    pool.drain(50.0);
+    assert_eq!(pool.current(), 0.0);
+  }
+
+  #[test]
+  fn restore_stops_at_the_maximum() {
+    let mut pool = Pool::new(90.0, 100.0);
+    pool.restore(50.0);
+    assert_eq!(pool.current(), 100.0);
+  }
+
+  #[test]
+  fn lowering_the_maximum_clamps_current() {
+    let mut pool = Pool::new(100.0, 100.0);
+    pool.set_max(60.0);
+    assert_eq!((pool.current(), pool.max()), (60.0, 60.0));
+
+    // raising it again doesn't refill
+    pool.set_max(120.0);
+    assert_eq!((pool.current(), pool.max()), (60.0, 120.0));
+
+    pool.set_max(-1.0);
+    assert_eq!((pool.current(), pool.max()), (0.0, 0.0));
+  }
+
+  #[test]
+  fn regenerates_a_fraction_of_the_maximum_per_second() {
+    let mut pool = Pool::new(0.0, 200.0);
+    pool.regenerate(0.1, 0.0, 0.5);
+    assert_eq!(pool.current(), 10.0);
+  }
+
+  #[test]
+  fn regeneration_waits_out_the_delay_after_spending() {
+    let mut pool = Pool::new(100.0, 100.0);
+    assert!(pool.try_spend(50.0));
+
+    pool.regenerate(0.1, 2.0, 1.5);
+    assert_eq!(pool.current(), 50.0);
+
+    pool.regenerate(0.1, 2.0, 1.0);
+    assert_eq!(pool.current(), 60.0);
+
+    // spending again restarts the wait
+    pool.drain(10.0);
+    pool.regenerate(0.1, 2.0, 1.0);
+    assert_eq!(pool.current(), 50.0);
+  }
+
+  #[test]
+  fn regeneration_stops_at_the_maximum() {
+    let mut pool = Pool::new(95.0, 100.0);
+    pool.regenerate(0.5, 0.0, 1.0);
+    assert_eq!(pool.current(), 100.0);
+  }
+}
@@ -7,25 +7,44 @@ use bevy::{
   window::WindowResolution,
 };
 use bevy_egui::EguiPlugin;
-use dialog::DialogBox;
 use game::{
   ui::{
     main_menu,
     settings_menu::{self, SaveSettingsEvent},
-    ui_playground, WindowEvent,
+    ui_playground, ApplySettingsEvent,
   },
   GameState,
 };
 use platform_dirs::AppDirs;
-use std::error::Error;
+use std::{
+  backtrace::Backtrace,
+  error::Error,
+  fs::{self, OpenOptions},
+  io::Write,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
 use storage::{Settings, SystemInformation};
 
 use crate::game::{
-  ui::{character_creation, character_selection},
-  SaveDataLoadedEvent, StartGameEvent,
+  classes::ClassPresets,
+  combat::{self, CombatConfig, DamageEvent},
+  display::{self, Monitors},
+  equipment, graphics,
+  items::{self, Items, PickupEvent},
+  magic::{self, Spells},
+  persistence::{self, SaveCompletedEvent, SaveFailedEvent, SaveGameEvent},
+  progression::{self, ExperienceEvent, LevelUpEvent, ProgressionConfig},
+  stats::{self, StatCurves},
+  ui::{
+    self as game_ui, character_creation, character_selection, error_modal, inventory, level_up,
+    pause_menu,
+  },
+  vitals::{self, VitalsConfig},
+  LoadPlayer, Overlay, QuitEvent, SaveDataLoadedEvent, StartGameEvent,
 };
 
-const GAME_NAME: &'static str = "M";
+const GAME_NAME: &str = "M";
 
 fn main() -> Result<(), Box<dyn Error>> {
   let game_dir = AppDirs::new(Some(GAME_NAME), true)
@@ -35,12 +54,21 @@ fn main() -> Result<(), Box<dyn Error>> {
   let game_saves_path = game_dir.join("saves");
   let settings_path = game_dir.join("settings.toml");
 
+  install_crash_log(game_dir.join("crash.log"));
+
   println!(
     "Saving all data to {}",
     game_dir.as_os_str().to_string_lossy()
   );
 
   let settings = Settings::load_or_default(&settings_path);
+  let class_presets = ClassPresets::load()?;
+  let stat_curves = StatCurves::load()?;
+  let vitals_config = VitalsConfig::load()?;
+  let progression_config = ProgressionConfig::load()?;
+  let combat_config = CombatConfig::load()?;
+  let spells = Spells::load()?;
+  let items = Items::load()?;
   let sys_info = SystemInformation::new(game_saves_path, settings_path, settings);
 
   App::new()
@@ -55,6 +83,7 @@ fn main() -> Result<(), Box<dyn Error>> {
               sys_info.settings.window.height as f32,
             ),
             position: WindowPosition::Centered(MonitorSelection::Primary),
+            present_mode: sys_info.settings.graphics.present_mode.into(),
             resizable: false,
             decorations: true,
             ..default()
@@ -72,13 +101,47 @@ fn main() -> Result<(), Box<dyn Error>> {
       EguiPlugin,
     ))
     .add_state::<GameState>()
-    .add_event::<WindowEvent>()
+    .add_state::<Overlay>()
+    .add_event::<ApplySettingsEvent>()
     .add_event::<SaveSettingsEvent>()
     .add_event::<StartGameEvent>()
     .add_event::<SaveDataLoadedEvent>()
+    .add_event::<SaveGameEvent>()
+    .add_event::<SaveCompletedEvent>()
+    .add_event::<SaveFailedEvent>()
+    .add_event::<ExperienceEvent>()
+    .add_event::<LevelUpEvent>()
+    .add_event::<DamageEvent>()
+    .add_event::<PickupEvent>()
+    .add_event::<QuitEvent>()
     // global
-    .add_systems(Startup, game::startup)
-    .add_systems(Update, game::global_input_handler)
+    .init_resource::<Monitors>()
+    .add_systems(
+      Startup,
+      (
+        game::startup,
+        (display::refresh_monitors, display::apply_saved_window).chain(),
+      ),
+    )
+    .add_systems(
+      Update,
+      (
+        game::global_input_handler,
+        persistence::poll_save_tasks,
+        // runs everywhere so reverts sent while leaving settings still land
+        ApplySettingsEvent::handler,
+        graphics::sync_shadows,
+        graphics::sync_render_scale,
+      ),
+    )
+    .add_systems(
+      Last,
+      (
+        display::sync_fullscreen,
+        graphics::limit_frame_rate,
+        persistence::save_on_exit,
+      ),
+    )
     // main menu
     .add_systems(OnEnter(GameState::MainMenu), main_menu::on_enter)
     .add_systems(
@@ -116,25 +179,138 @@ fn main() -> Result<(), Box<dyn Error>> {
     .add_systems(OnEnter(GameState::StartGame), StartGameEvent::handle)
     .add_systems(
       Update,
-      SaveDataLoadedEvent::handle.run_if(in_state(GameState::StartGame)),
+      (LoadPlayer::poll, SaveDataLoadedEvent::handle)
+        .chain()
+        .run_if(in_state(GameState::StartGame)),
     )
+    // load errors
+    .add_systems(
+      Update,
+      error_modal::on_update.run_if(in_state(GameState::ErrorModal)),
+    )
+    .add_systems(OnExit(GameState::ErrorModal), error_modal::on_exit)
     // play game
-    .add_systems(OnEnter(GameState::Gameplay), game::on_enter)
+    .add_systems(
+      OnEnter(GameState::Gameplay),
+      (game::on_enter, game_ui::on_enter),
+    )
+    .add_systems(
+      Update,
+      (
+        equipment::tick_buffs,
+        equipment::apply_modifiers,
+        stats::recalculate_stats,
+        vitals::sync_maxima,
+        // the world stands still behind every overlay, not just pause
+        vitals::regenerate.run_if(in_state(Overlay::None)),
+        (
+          game::player_movement_system,
+          game::focus_camera_system,
+          combat::attack_input,
+          magic::cast_input,
+          items::pickup_input,
+          items::inventory_input,
+          progression::level_up_input,
+        )
+          .chain()
+          .run_if(in_state(Overlay::None)),
+        (combat::advance_attacks, combat::resolve_hits)
+          .chain()
+          .run_if(in_state(Overlay::None)),
+        (
+          magic::tick_cooldowns,
+          magic::move_projectiles,
+          magic::expire_wards,
+        )
+          .chain()
+          .run_if(in_state(Overlay::None)),
+        DamageEvent::handler,
+        ExperienceEvent::handler,
+      )
+        .chain()
+        .run_if(in_state(GameState::Gameplay))
+        .run_if(not(in_state(Overlay::Pause))),
+    )
+    .add_systems(
+      Update,
+      (
+        game::track_play_time.run_if(not(in_state(Overlay::Pause))),
+        (
+          persistence::autosave_system.run_if(not(in_state(Overlay::Pause))),
+          persistence::manual_save_input,
+          SaveGameEvent::handler,
+        )
+          .chain(),
+        (game_ui::notify, game_ui::on_update).chain(),
+        (game::pause_input, QuitEvent::handler).chain(),
+      )
+        .run_if(in_state(GameState::Gameplay)),
+    )
+    .add_systems(
+      OnExit(GameState::Gameplay),
+      (game::on_exit, game_ui::on_exit),
+    )
+    // level up overlay
+    .add_systems(
+      OnEnter(Overlay::LevelUp),
+      (game::release_cursor, level_up::on_enter),
+    )
     .add_systems(
       Update,
-      (game::player_movement_system, game::focus_camera_system)
+      (level_up::close_input, level_up::on_update)
         .chain()
+        .run_if(in_state(Overlay::LevelUp))
+        .run_if(in_state(GameState::Gameplay)),
+    )
+    .add_systems(
+      OnExit(Overlay::LevelUp),
+      (game::restore_cursor, level_up::on_exit),
+    )
+    // inventory overlay
+    .add_systems(
+      OnEnter(Overlay::Inventory),
+      (game::release_cursor, inventory::on_enter),
+    )
+    .add_systems(
+      Update,
+      inventory::on_update
+        .run_if(in_state(Overlay::Inventory))
         .run_if(in_state(GameState::Gameplay)),
     )
-    .add_systems(OnExit(GameState::Gameplay), game::on_exit)
+    .add_systems(
+      OnExit(Overlay::Inventory),
+      (game::restore_cursor, inventory::on_exit),
+    )
+    // pause overlay
+    .add_systems(
+      OnEnter(Overlay::Pause),
+      (game::release_cursor, pause_menu::on_enter),
+    )
+    .add_systems(
+      Update,
+      pause_menu::on_update
+        .run_if(in_state(Overlay::Pause))
+        .run_if(in_state(GameState::Gameplay)),
+    )
+    .add_systems(
+      OnExit(Overlay::Pause),
+      (game::restore_cursor, pause_menu::on_exit),
+    )
     // settings
-    .add_systems(OnEnter(GameState::SettingsMenu), settings_menu::on_enter)
+    .add_systems(
+      OnEnter(GameState::SettingsMenu),
+      (display::refresh_monitors, settings_menu::on_enter),
+    )
     .add_systems(
       Update,
       (
-        settings_menu::on_update,
+        (
+          settings_menu::tick_confirmation,
+          settings_menu::capture_rebind,
+          settings_menu::on_update,
+        )
+          .chain(),
         SaveSettingsEvent::handler,
-        WindowEvent::handler,
       )
         .run_if(in_state(GameState::SettingsMenu)),
     )
@@ -146,17 +322,40 @@ fn main() -> Result<(), Box<dyn Error>> {
       ui_playground::on_update.run_if(in_state(GameState::UiPlayground)),
     )
     .add_systems(OnExit(GameState::UiPlayground), ui_playground::on_exit)
+    .insert_resource(sys_info.settings.graphics.msaa())
     .insert_resource(sys_info)
+    .insert_resource(class_presets)
+    .insert_resource(stat_curves)
+    .insert_resource(vitals_config)
+    .insert_resource(progression_config)
+    .insert_resource(combat_config)
+    .insert_resource(spells)
+    .insert_resource(items)
     .run();
 
   Ok(())
 }
 
-pub fn fatal_error(msg: &str) -> ! {
-  error!("{}", msg);
-  dialog::Message::new(msg)
-    .title("Fatal Error")
-    .show()
-    .expect("failed to show dialog box");
-  panic!("{}", msg);
+// appends every panic to the crash log
+fn install_crash_log(path: PathBuf) {
+  let default_hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |info| {
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|time| time.as_secs())
+      .unwrap_or_default();
+    let entry = format!("[{}] {}\n{}\n", timestamp, info, Backtrace::force_capture());
+
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).ok();
+    }
+    match OpenOptions::new().create(true).append(true).open(&path) {
+      Ok(mut file) => {
+        file.write_all(entry.as_bytes()).ok();
+      }
+      Err(e) => eprintln!("failed to write crash log {}: {}", path.display(), e),
+    }
+
+    default_hook(info);
+  }));
 }
@@ -1,5 +1,6 @@
 pub mod saves;
 pub mod settings;
+pub mod writer;
 
 use anyhow::Result;
 use bevy::prelude::*;
@@ -25,6 +26,10 @@ impl SystemInformation {
     }
   }
 
+  pub fn save_path(&self, id: &str) -> PathBuf {
+    saves::file_path(&self.game_saves_path, id)
+  }
+
   pub fn save_settings(&self) -> Result<()> {
     self.settings.save(&self.settings_path)
   }
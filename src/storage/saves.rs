@@ -1,14 +1,97 @@
+pub mod migrations;
+pub mod names;
+
+use super::writer;
+use anyhow::{anyhow, Result};
+use bevy::log::warn;
 use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{Display, Formatter, Result as FmtResult},
+  fs, io,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use strum_macros::EnumIter;
+
+pub const EXTENSION: &str = "ms";
 
-#[derive(Serialize, Deserialize)]
+// the fields read by `SaveSummary` must stay at the front, in order
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SaveData {
   version: u16,
   pub name: String,
   pub attributes: Attributes,
+  pub play_time: Duration,
+  // seconds since the unix epoch, zero when unknown
+  pub last_played: u64,
+  // none until the character has been saved in the world
+  pub player: Option<PlayerState>,
+  pub camera: CameraState,
+  // id of the class preset chosen at creation, none for characters made before classes
+  pub class: Option<String>,
+  // none restores every vital to full
+  pub vitals: Option<VitalsState>,
+  pub progression: ProgressionState,
+  pub spells: SpellsState,
+  pub inventory: Vec<ItemStack>,
+  // item ids by the slot they are worn in
+  pub equipment: BTreeMap<EquipSlot, String>,
 }
 
 impl SaveData {
-  pub const LATEST: u16 = 1;
+  pub const LATEST: u16 = 9;
+  pub const BACKUPS: usize = 3;
+
+  // decodes any known save version, upgrading it to the latest layout
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+    migrations::migrate(bytes)
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(self)?)
+  }
+
+  pub fn load(file: &Path) -> Result<Self> {
+    Self::from_bytes(&fs::read(file)?)
+  }
+
+  // falls back to the rotated backups, newest first, when the primary file is corrupt or gone,
+  // one that's there but can't be read may be fine on a retry so the player gets to choose
+  pub fn load_or_backup(file: &Path) -> Result<Self> {
+    let err = match Self::load(file) {
+      Ok(data) => return Ok(data),
+      Err(err) => err,
+    };
+
+    warn!("failed to load {}: {}", file.display(), err);
+    if err
+      .downcast_ref::<io::Error>()
+      .is_some_and(|e| e.kind() != io::ErrorKind::NotFound)
+    {
+      return Err(err);
+    }
+
+    for backup in writer::backups(file, Self::BACKUPS) {
+      match Self::load(&backup) {
+        Ok(data) => {
+          warn!("restored save data from {}", backup.display());
+          return Ok(data);
+        }
+        Err(e) => warn!("failed to load backup {}: {}", backup.display(), e),
+      }
+    }
+
+    Err(err)
+  }
+
+  pub fn exists(file: &Path) -> bool {
+    file.exists() || has_backups(file)
+  }
+
+  pub fn save(&self, file: &Path) -> Result<()> {
+    writer::write(file, &self.to_bytes()?, Self::BACKUPS)
+  }
 }
 
 // different than gameplay attributes
@@ -26,6 +109,34 @@ pub struct Attributes {
   pub mind: u32,
 }
 
+impl Attributes {
+  pub fn get(&self, kind: AttributeKind) -> u32 {
+    match kind {
+      AttributeKind::Vitality => self.vitality,
+      AttributeKind::Endurance => self.endurance,
+      AttributeKind::Strength => self.strength,
+      AttributeKind::Dexterity => self.dexterity,
+      AttributeKind::Agility => self.agility,
+      AttributeKind::Intelligence => self.intelligence,
+      AttributeKind::Wisdom => self.wisdom,
+      AttributeKind::Mind => self.mind,
+    }
+  }
+
+  pub fn get_mut(&mut self, kind: AttributeKind) -> &mut u32 {
+    match kind {
+      AttributeKind::Vitality => &mut self.vitality,
+      AttributeKind::Endurance => &mut self.endurance,
+      AttributeKind::Strength => &mut self.strength,
+      AttributeKind::Dexterity => &mut self.dexterity,
+      AttributeKind::Agility => &mut self.agility,
+      AttributeKind::Intelligence => &mut self.intelligence,
+      AttributeKind::Wisdom => &mut self.wisdom,
+      AttributeKind::Mind => &mut self.mind,
+    }
+  }
+}
+
 impl Default for Attributes {
   fn default() -> Self {
     Self {
@@ -41,6 +152,337 @@ impl Default for Attributes {
   }
 }
 
+// the leading fields of a save, enough to describe a character
+// without decoding the rest of the file
+#[derive(Deserialize)]
+pub struct SaveSummary {
+  // only there to keep the fields after it in place
+  _version: u16,
+  pub name: String,
+  pub attributes: Attributes,
+  pub play_time: Duration,
+  pub last_played: u64,
+}
+
+impl SaveSummary {
+  // first version laid out with the summary fields at the front
+  const SINCE: u16 = 3;
+
+  pub fn load(file: &Path) -> Result<Self> {
+    let bytes = fs::read(file)?;
+    let header = migrations::SaveHeader::read(&bytes)?;
+    if (Self::SINCE..=SaveData::LATEST).contains(&header.version) {
+      Ok(bincode::deserialize(&bytes)?)
+    } else {
+      Ok(SaveData::from_bytes(&bytes)?.into())
+    }
+  }
+}
+
+impl From<SaveData> for SaveSummary {
+  fn from(data: SaveData) -> Self {
+    Self {
+      _version: data.version,
+      name: data.name,
+      attributes: data.attributes,
+      play_time: data.play_time,
+      last_played: data.last_played,
+    }
+  }
+}
+
+pub fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|time| time.as_secs())
+    .unwrap_or_default()
+}
+
+// saves are stored by id so the character's name never touches the file system
+pub fn file_path(dir: &Path, id: &str) -> PathBuf {
+  dir.join(format!("{}.{}", id, EXTENSION))
+}
+
+// a save id not yet used in `dir`
+pub fn generate_id(dir: &Path) -> String {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|time| time.as_nanos())
+    .unwrap_or_default();
+
+  (0..)
+    .map(|offset| format!("{:x}", nanos + offset))
+    .find(|id| !SaveData::exists(&file_path(dir, id)))
+    .unwrap_or_default()
+}
+
+// display names of every character in `dir`
+pub fn names(dir: &Path) -> Vec<String> {
+  list(dir)
+    .iter()
+    .filter_map(|file| SaveSummary::load(file).ok())
+    .map(|summary| summary.name)
+    .collect()
+}
+
+fn validate_new_name(dir: &Path, name: &str, ignore: Option<&str>) -> Result<()> {
+  let taken = names(dir);
+  let taken = taken
+    .iter()
+    .filter(|other| Some(other.as_str()) != ignore)
+    .map(String::as_str);
+  names::validate(name, taken).map_err(|e| anyhow!("{}", e))
+}
+
+// every save file in `dir`, backups excluded
+pub fn list(dir: &Path) -> Vec<PathBuf> {
+  let Ok(entries) = dir.read_dir() else {
+    return Vec::new();
+  };
+
+  entries
+    .flatten()
+    .filter(|entry| {
+      entry
+        .file_type()
+        .map(|kind| kind.is_file())
+        .unwrap_or_default()
+    })
+    .map(|entry| entry.path())
+    .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+    .collect()
+}
+
+// renames the character, the save keeps its id and file
+pub fn rename(dir: &Path, id: &str, new_name: &str) -> Result<PathBuf> {
+  let file = file_path(dir, id);
+  let mut data = SaveData::load(&file)?;
+  validate_new_name(dir, new_name, Some(&data.name))?;
+
+  data.name = new_name.to_string();
+  data.save(&file)?;
+  Ok(file)
+}
+
+// copies the save into a new character called `new_name`
+pub fn duplicate(dir: &Path, id: &str, new_name: &str) -> Result<PathBuf> {
+  validate_new_name(dir, new_name, None)?;
+
+  let mut data = SaveData::load(&file_path(dir, id))?;
+  data.name = new_name.to_string();
+
+  let to = file_path(dir, &generate_id(dir));
+  data.save(&to)?;
+  Ok(to)
+}
+
+// writes a copy of the save to `dest`, which may be a directory
+pub fn export(dir: &Path, id: &str, dest: &Path) -> Result<PathBuf> {
+  // decode first so only valid, up to date saves leave the game
+  let data = SaveData::load(&file_path(dir, id))?;
+
+  // named by id like in the saves directory, display names can clash
+  let dest = if dest.is_dir() {
+    file_path(dest, id)
+  } else {
+    dest.to_path_buf()
+  };
+
+  writer::write(&dest, &data.to_bytes()?, 0)?;
+  Ok(dest)
+}
+
+// copies the save at `src` into `dir` under a fresh id
+pub fn import(dir: &Path, src: &Path) -> Result<PathBuf> {
+  let data = SaveData::load(src)?;
+  validate_new_name(dir, &data.name, None)?;
+
+  let to = file_path(dir, &generate_id(dir));
+  data.save(&to)?;
+  Ok(to)
+}
+
+pub fn has_backups(file: &Path) -> bool {
+  writer::backups(file, SaveData::BACKUPS).next().is_some()
+}
+
+// a backup that loads, with when it was saved
+pub struct Backup {
+  pub path: PathBuf,
+  pub last_played: u64,
+}
+
+// the newest backup of `file` that still loads
+pub fn usable_backup(file: &Path) -> Option<Backup> {
+  writer::backups(file, SaveData::BACKUPS).find_map(|path| match SaveData::load(&path) {
+    Ok(data) => Some(Backup {
+      path,
+      last_played: data.last_played,
+    }),
+    Err(e) => {
+      warn!("backup {} is unusable: {}", path.display(), e);
+      None
+    }
+  })
+}
+
+// replaces the primary file with `backup`, leaving the other backups as they are
+pub fn restore_backup(file: &Path, backup: &Backup) -> Result<()> {
+  let data = SaveData::load(&backup.path)?;
+  writer::write(file, &data.to_bytes()?, 0)
+}
+
+// removes the save along with all of its backups
+pub fn delete(file: &Path) -> Result<()> {
+  let backups = writer::backups(file, SaveData::BACKUPS).collect::<Vec<_>>();
+  if file.exists() {
+    fs::remove_file(file)?;
+  }
+  for backup in backups {
+    fs::remove_file(backup)?;
+  }
+  Ok(())
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PlayerState {
+  pub translation: [f32; 3],
+  pub rotation: [f32; 4],
+}
+
+// current values, the maxima are derived from attributes on load
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct VitalsState {
+  pub health: f32,
+  pub stamina: f32,
+  pub mana: f32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ProgressionState {
+  pub level: u32,
+  // earned toward the next level
+  pub experience: u64,
+  pub unspent_points: u32,
+}
+
+impl Default for ProgressionState {
+  fn default() -> Self {
+    Self {
+      level: 1,
+      experience: 0,
+      unspent_points: 0,
+    }
+  }
+}
+
+// spell ids, see spells.toml
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct SpellsState {
+  pub known: Vec<String>,
+  // in slot order, may hold more than the character currently has slots for
+  pub memorized: Vec<String>,
+}
+
+// a number of one item, by id, see items.toml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemStack {
+  pub id: String,
+  pub count: u32,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CameraMode {
+  #[default]
+  FirstPerson,
+  ThirdPerson,
+}
+
+impl CameraMode {
+  pub fn toggled(self) -> Self {
+    match self {
+      Self::FirstPerson => Self::ThirdPerson,
+      Self::ThirdPerson => Self::FirstPerson,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct CameraState {
+  pub mode: CameraMode,
+  pub yaw: f32,
+  pub pitch: f32,
+}
+
+impl Default for CameraState {
+  fn default() -> Self {
+    Self {
+      mode: CameraMode::default(),
+      yaw: 90.0,
+      pitch: 0.0,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+  Vitality,
+  Endurance,
+  Strength,
+  Dexterity,
+  Agility,
+  Intelligence,
+  Wisdom,
+  Mind,
+}
+
+impl Display for AttributeKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      AttributeKind::Vitality => write!(f, "Vitality"),
+      AttributeKind::Endurance => write!(f, "Endurance"),
+      AttributeKind::Strength => write!(f, "Strength"),
+      AttributeKind::Dexterity => write!(f, "Dexterity"),
+      AttributeKind::Agility => write!(f, "Agility"),
+      AttributeKind::Intelligence => write!(f, "Intelligence"),
+      AttributeKind::Wisdom => write!(f, "Wisdom"),
+      AttributeKind::Mind => write!(f, "Mind"),
+    }
+  }
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, EnumIter,
+)]
+pub enum EquipSlot {
+  Weapon,
+  Offhand,
+  Head,
+  Chest,
+  Hands,
+  Legs,
+  Feet,
+  LeftRing,
+  RightRing,
+}
+
+impl Display for EquipSlot {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      EquipSlot::Weapon => write!(f, "Weapon"),
+      EquipSlot::Offhand => write!(f, "Offhand"),
+      EquipSlot::Head => write!(f, "Head"),
+      EquipSlot::Chest => write!(f, "Chest"),
+      EquipSlot::Hands => write!(f, "Hands"),
+      EquipSlot::Legs => write!(f, "Legs"),
+      EquipSlot::Feet => write!(f, "Feet"),
+      EquipSlot::LeftRing => write!(f, "Left Ring"),
+      EquipSlot::RightRing => write!(f, "Right Ring"),
+    }
+  }
+}
+
 pub struct SaveDataBuilder {
   data: SaveData,
 }
@@ -52,6 +494,16 @@ impl SaveDataBuilder {
         version: SaveData::LATEST,
         name: String::default(),
         attributes: Attributes::default(),
+        play_time: Duration::ZERO,
+        last_played: unix_now(),
+        player: None,
+        camera: CameraState::default(),
+        class: None,
+        vitals: None,
+        progression: ProgressionState::default(),
+        spells: SpellsState::default(),
+        inventory: Vec::new(),
+        equipment: BTreeMap::new(),
       },
     }
   }
@@ -66,7 +518,229 @@ impl SaveDataBuilder {
     self
   }
 
+  pub fn play_time(mut self, play_time: Duration) -> Self {
+    self.data.play_time = play_time;
+    self
+  }
+
+  pub fn player(mut self, player: PlayerState) -> Self {
+    self.data.player = Some(player);
+    self
+  }
+
+  pub fn camera(mut self, camera: CameraState) -> Self {
+    self.data.camera = camera;
+    self
+  }
+
+  pub fn class(mut self, class: Option<String>) -> Self {
+    self.data.class = class;
+    self
+  }
+
+  pub fn vitals(mut self, vitals: VitalsState) -> Self {
+    self.data.vitals = Some(vitals);
+    self
+  }
+
+  pub fn progression(mut self, progression: ProgressionState) -> Self {
+    self.data.progression = progression;
+    self
+  }
+
+  pub fn spells(mut self, spells: SpellsState) -> Self {
+    self.data.spells = spells;
+    self
+  }
+
+  pub fn inventory(mut self, inventory: Vec<ItemStack>) -> Self {
+    self.data.inventory = inventory;
+    self
+  }
+
+  pub fn equipment(mut self, equipment: BTreeMap<EquipSlot, String>) -> Self {
+    self.data.equipment = equipment;
+    self
+  }
+
   pub fn build(self) -> SaveData {
     self.data
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  // a fresh character in `dir`, returning its id
+  fn create(dir: &Path, name: &str) -> String {
+    let id = generate_id(dir);
+    SaveDataBuilder::new()
+      .name(name.to_string())
+      .build()
+      .save(&file_path(dir, &id))
+      .unwrap();
+    id
+  }
+
+  fn name_of(file: &Path) -> String {
+    SaveData::load(file).unwrap().name
+  }
+
+  #[test]
+  fn rename_keeps_the_file() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    let file = rename(dir.path(), &id, "Bran").unwrap();
+    assert_eq!(file, file_path(dir.path(), &id));
+    assert_eq!(name_of(&file), "Bran");
+    assert_eq!(list(dir.path()).len(), 1);
+  }
+
+  #[test]
+  fn rename_rejects_a_taken_name_in_any_case() {
+    let dir = TempDir::new().unwrap();
+    create(dir.path(), "Aria");
+    let id = create(dir.path(), "Bran");
+
+    assert!(rename(dir.path(), &id, "ARIA").is_err());
+    assert_eq!(name_of(&file_path(dir.path(), &id)), "Bran");
+  }
+
+  #[test]
+  fn rename_may_change_the_case_of_its_own_name() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    rename(dir.path(), &id, "ARIA").unwrap();
+    assert_eq!(name_of(&file_path(dir.path(), &id)), "ARIA");
+  }
+
+  #[test]
+  fn duplicate_makes_a_new_save() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    let copy = duplicate(dir.path(), &id, "Cara").unwrap();
+    assert_ne!(copy, file_path(dir.path(), &id));
+    assert_eq!(name_of(&copy), "Cara");
+    assert_eq!(name_of(&file_path(dir.path(), &id)), "Aria");
+    assert_eq!(list(dir.path()).len(), 2);
+  }
+
+  #[test]
+  fn duplicate_rejects_a_taken_name() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    assert!(duplicate(dir.path(), &id, "aria").is_err());
+    assert_eq!(list(dir.path()).len(), 1);
+  }
+
+  #[test]
+  fn import_rejects_a_corrupt_file() {
+    let dir = TempDir::new().unwrap();
+    let src = TempDir::new().unwrap();
+    let corrupt = src.path().join("corrupt.ms");
+    fs::write(&corrupt, b"not a save").unwrap();
+
+    assert!(import(dir.path(), &corrupt).is_err());
+    assert!(list(dir.path()).is_empty());
+  }
+
+  #[test]
+  fn import_rejects_a_taken_name() {
+    let dir = TempDir::new().unwrap();
+    let other = TempDir::new().unwrap();
+    create(dir.path(), "Aria");
+    let id = create(other.path(), "aria");
+
+    assert!(import(dir.path(), &file_path(other.path(), &id)).is_err());
+    assert_eq!(list(dir.path()).len(), 1);
+  }
+
+  #[test]
+  fn export_to_a_file_path() {
+    let dir = TempDir::new().unwrap();
+    let out = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    let dest = out.path().join("nested").join("backup.ms");
+    assert_eq!(export(dir.path(), &id, &dest).unwrap(), dest);
+    assert_eq!(name_of(&dest), "Aria");
+  }
+
+  #[test]
+  fn export_to_a_directory() {
+    let dir = TempDir::new().unwrap();
+    let out = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    let dest = export(dir.path(), &id, out.path()).unwrap();
+    assert_eq!(dest, file_path(out.path(), &id));
+    assert_eq!(name_of(&dest), "Aria");
+  }
+
+  #[test]
+  fn exports_of_like_named_characters_stay_apart() {
+    let dir = TempDir::new().unwrap();
+    let out = TempDir::new().unwrap();
+    let first = create(dir.path(), "Aria");
+    let second = create(dir.path(), "ARIA");
+
+    export(dir.path(), &first, out.path()).unwrap();
+    export(dir.path(), &second, out.path()).unwrap();
+    assert_eq!(list(out.path()).len(), 2);
+  }
+
+  #[test]
+  fn export_then_import_round_trips() {
+    let dir = TempDir::new().unwrap();
+    let other = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+
+    let exported = export(dir.path(), &id, &other.path().join("aria.ms")).unwrap();
+    let imported = import(&other.path().join("saves"), &exported).unwrap();
+    assert_eq!(name_of(&imported), "Aria");
+  }
+
+  #[test]
+  fn delete_removes_the_backups() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+    let file = file_path(dir.path(), &id);
+    let data = SaveData::load(&file).unwrap();
+    for _ in 0..SaveData::BACKUPS {
+      data.save(&file).unwrap();
+    }
+    assert_eq!(writer::backups(&file, SaveData::BACKUPS).count(), 3);
+
+    delete(&file).unwrap();
+    assert!(!SaveData::exists(&file));
+    assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+  }
+
+  #[test]
+  fn delete_removes_backups_past_a_gap() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+    let file = file_path(dir.path(), &id);
+    fs::copy(&file, writer::backup_path(&file, 2)).unwrap();
+
+    delete(&file).unwrap();
+    assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+  }
+
+  #[test]
+  fn corrupt_saves_fall_back_to_a_backup() {
+    let dir = TempDir::new().unwrap();
+    let id = create(dir.path(), "Aria");
+    let file = file_path(dir.path(), &id);
+    SaveData::load(&file).unwrap().save(&file).unwrap();
+    fs::write(&file, b"not a save").unwrap();
+
+    assert_eq!(SaveData::load_or_backup(&file).unwrap().name, "Aria");
+  }
+}
@@ -0,0 +1,642 @@
+use super::{
+  Attributes, CameraMode, CameraState, ItemStack, PlayerState, ProgressionState, SaveData,
+  SpellsState, VitalsState,
+};
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{collections::BTreeMap, time::Duration};
+
+// every save layout starts with its version, so the header can be read
+// from any save file regardless of the layout that follows it
+#[derive(Deserialize)]
+pub struct SaveHeader {
+  pub version: u16,
+}
+
+impl SaveHeader {
+  pub fn read(bytes: &[u8]) -> Result<Self> {
+    Ok(bincode::deserialize(bytes)?)
+  }
+}
+
+// when bumping SaveData::LATEST:
+// 1. freeze the outgoing layout below as `SaveDataV{n}`, along with copies of any types it
+//    holds that aren't frozen yet, so later edits to the live types can't change old layouts
+// 2. implement `From<SaveDataV{n}>` for the next version
+// 3. add an arm to `migrate` decoding the frozen layout
+// 4. check in a `fixtures/v{n}.ms` written by the outgoing version and add it to the tests
+pub fn migrate(bytes: &[u8]) -> Result<SaveData> {
+  let header = SaveHeader::read(bytes)?;
+  match header.version {
+    1 => decode::<SaveDataV1>(bytes),
+    2 => decode::<SaveDataV2>(bytes),
+    3 => decode::<SaveDataV3>(bytes),
+    4 => decode::<SaveDataV4>(bytes),
+    5 => decode::<SaveDataV5>(bytes),
+    6 => decode::<SaveDataV6>(bytes),
+    7 => decode::<SaveDataV7>(bytes),
+    8 => decode::<SaveDataV8>(bytes),
+    SaveData::LATEST => decode::<SaveData>(bytes),
+    version => Err(anyhow!(
+      "save version {} is not supported (latest is {})",
+      version,
+      SaveData::LATEST
+    )),
+  }
+}
+
+fn decode<T>(bytes: &[u8]) -> Result<SaveData>
+where
+  T: DeserializeOwned + Into<SaveData>,
+{
+  Ok(bincode::deserialize::<T>(bytes)?.into())
+}
+
+// frozen types are named after the version that introduced them and shared by every version
+// after it until their layout changes
+
+#[derive(Deserialize)]
+struct AttributesV1 {
+  vitality: u32,
+  endurance: u32,
+  strength: u32,
+  dexterity: u32,
+  agility: u32,
+  intelligence: u32,
+  wisdom: u32,
+  mind: u32,
+}
+
+impl From<AttributesV1> for Attributes {
+  fn from(attributes: AttributesV1) -> Self {
+    Self {
+      vitality: attributes.vitality,
+      endurance: attributes.endurance,
+      strength: attributes.strength,
+      dexterity: attributes.dexterity,
+      agility: attributes.agility,
+      intelligence: attributes.intelligence,
+      wisdom: attributes.wisdom,
+      mind: attributes.mind,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct PlayerStateV2 {
+  translation: [f32; 3],
+  rotation: [f32; 4],
+}
+
+impl From<PlayerStateV2> for PlayerState {
+  fn from(player: PlayerStateV2) -> Self {
+    Self {
+      translation: player.translation,
+      rotation: player.rotation,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+enum CameraModeV2 {
+  FirstPerson,
+  ThirdPerson,
+}
+
+impl From<CameraModeV2> for CameraMode {
+  fn from(mode: CameraModeV2) -> Self {
+    match mode {
+      CameraModeV2::FirstPerson => Self::FirstPerson,
+      CameraModeV2::ThirdPerson => Self::ThirdPerson,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct CameraStateV2 {
+  mode: CameraModeV2,
+  yaw: f32,
+  pitch: f32,
+}
+
+// what version 2 gave saves from before cameras were stored
+impl Default for CameraStateV2 {
+  fn default() -> Self {
+    Self {
+      mode: CameraModeV2::FirstPerson,
+      yaw: 90.0,
+      pitch: 0.0,
+    }
+  }
+}
+
+impl From<CameraStateV2> for CameraState {
+  fn from(camera: CameraStateV2) -> Self {
+    Self {
+      mode: camera.mode.into(),
+      yaw: camera.yaw,
+      pitch: camera.pitch,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct VitalsStateV5 {
+  health: f32,
+  stamina: f32,
+  mana: f32,
+}
+
+impl From<VitalsStateV5> for VitalsState {
+  fn from(vitals: VitalsStateV5) -> Self {
+    Self {
+      health: vitals.health,
+      stamina: vitals.stamina,
+      mana: vitals.mana,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct ProgressionStateV6 {
+  level: u32,
+  experience: u64,
+  unspent_points: u32,
+}
+
+// what version 6 gave saves from before progression was stored
+impl Default for ProgressionStateV6 {
+  fn default() -> Self {
+    Self {
+      level: 1,
+      experience: 0,
+      unspent_points: 0,
+    }
+  }
+}
+
+impl From<ProgressionStateV6> for ProgressionState {
+  fn from(progression: ProgressionStateV6) -> Self {
+    Self {
+      level: progression.level,
+      experience: progression.experience,
+      unspent_points: progression.unspent_points,
+    }
+  }
+}
+
+#[derive(Default, Deserialize)]
+struct SpellsStateV7 {
+  known: Vec<String>,
+  memorized: Vec<String>,
+}
+
+impl From<SpellsStateV7> for SpellsState {
+  fn from(spells: SpellsStateV7) -> Self {
+    Self {
+      known: spells.known,
+      memorized: spells.memorized,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct ItemStackV8 {
+  id: String,
+  count: u32,
+}
+
+impl From<ItemStackV8> for ItemStack {
+  fn from(stack: ItemStackV8) -> Self {
+    Self {
+      id: stack.id,
+      count: stack.count,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV1 {
+  // only there to keep the fields after it in place
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+}
+
+impl From<SaveDataV1> for SaveData {
+  fn from(save: SaveDataV1) -> Self {
+    SaveDataV2::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV2 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+}
+
+impl From<SaveDataV1> for SaveDataV2 {
+  fn from(save: SaveDataV1) -> Self {
+    Self {
+      _version: 2,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: Duration::ZERO,
+      player: None,
+      camera: CameraStateV2::default(),
+    }
+  }
+}
+
+impl From<SaveDataV2> for SaveData {
+  fn from(save: SaveDataV2) -> Self {
+    SaveDataV3::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV3 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+}
+
+impl From<SaveDataV2> for SaveDataV3 {
+  fn from(save: SaveDataV2) -> Self {
+    Self {
+      _version: 3,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: 0,
+      player: save.player,
+      camera: save.camera,
+    }
+  }
+}
+
+impl From<SaveDataV3> for SaveData {
+  fn from(save: SaveDataV3) -> Self {
+    SaveDataV4::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV4 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+  class: Option<String>,
+}
+
+impl From<SaveDataV3> for SaveDataV4 {
+  fn from(save: SaveDataV3) -> Self {
+    Self {
+      _version: 4,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player,
+      camera: save.camera,
+      class: None,
+    }
+  }
+}
+
+impl From<SaveDataV4> for SaveData {
+  fn from(save: SaveDataV4) -> Self {
+    SaveDataV5::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV5 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+  class: Option<String>,
+  vitals: Option<VitalsStateV5>,
+}
+
+impl From<SaveDataV4> for SaveDataV5 {
+  fn from(save: SaveDataV4) -> Self {
+    Self {
+      _version: 5,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player,
+      camera: save.camera,
+      class: save.class,
+      vitals: None,
+    }
+  }
+}
+
+impl From<SaveDataV5> for SaveData {
+  fn from(save: SaveDataV5) -> Self {
+    SaveDataV6::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV6 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+  class: Option<String>,
+  vitals: Option<VitalsStateV5>,
+  progression: ProgressionStateV6,
+}
+
+impl From<SaveDataV5> for SaveDataV6 {
+  fn from(save: SaveDataV5) -> Self {
+    Self {
+      _version: 6,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player,
+      camera: save.camera,
+      class: save.class,
+      vitals: save.vitals,
+      progression: ProgressionStateV6::default(),
+    }
+  }
+}
+
+impl From<SaveDataV6> for SaveData {
+  fn from(save: SaveDataV6) -> Self {
+    SaveDataV7::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV7 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+  class: Option<String>,
+  vitals: Option<VitalsStateV5>,
+  progression: ProgressionStateV6,
+  spells: SpellsStateV7,
+}
+
+impl From<SaveDataV6> for SaveDataV7 {
+  fn from(save: SaveDataV6) -> Self {
+    Self {
+      _version: 7,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player,
+      camera: save.camera,
+      class: save.class,
+      vitals: save.vitals,
+      progression: save.progression,
+      spells: SpellsStateV7::default(),
+    }
+  }
+}
+
+impl From<SaveDataV7> for SaveData {
+  fn from(save: SaveDataV7) -> Self {
+    SaveDataV8::from(save).into()
+  }
+}
+
+#[derive(Deserialize)]
+struct SaveDataV8 {
+  _version: u16,
+  name: String,
+  attributes: AttributesV1,
+  play_time: Duration,
+  last_played: u64,
+  player: Option<PlayerStateV2>,
+  camera: CameraStateV2,
+  class: Option<String>,
+  vitals: Option<VitalsStateV5>,
+  progression: ProgressionStateV6,
+  spells: SpellsStateV7,
+  inventory: Vec<ItemStackV8>,
+}
+
+impl From<SaveDataV7> for SaveDataV8 {
+  fn from(save: SaveDataV7) -> Self {
+    Self {
+      _version: 8,
+      name: save.name,
+      attributes: save.attributes,
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player,
+      camera: save.camera,
+      class: save.class,
+      vitals: save.vitals,
+      progression: save.progression,
+      spells: save.spells,
+      inventory: Vec::new(),
+    }
+  }
+}
+
+impl From<SaveDataV8> for SaveData {
+  fn from(save: SaveDataV8) -> Self {
+    Self {
+      version: SaveData::LATEST,
+      name: save.name,
+      attributes: save.attributes.into(),
+      play_time: save.play_time,
+      last_played: save.last_played,
+      player: save.player.map(Into::into),
+      camera: save.camera.into(),
+      class: save.class,
+      vitals: save.vitals.map(Into::into),
+      progression: save.progression.into(),
+      spells: save.spells.into(),
+      inventory: save.inventory.into_iter().map(Into::into).collect(),
+      equipment: BTreeMap::new(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::storage::saves::SaveDataBuilder;
+
+  // every fixture holds the same character, with as much of it as its version could store
+  fn check(bytes: &[u8], since: u16) {
+    let data = migrate(bytes).unwrap();
+    assert_eq!(data.version, SaveData::LATEST);
+    assert_eq!(data.name, "Aria");
+
+    let a = &data.attributes;
+    assert_eq!(
+      [
+        a.vitality,
+        a.endurance,
+        a.strength,
+        a.dexterity,
+        a.agility,
+        a.intelligence,
+        a.wisdom,
+        a.mind
+      ],
+      [3, 4, 5, 6, 7, 8, 9, 10]
+    );
+
+    if since >= 2 {
+      assert_eq!(data.play_time, Duration::new(3723, 500_000_000));
+      let player = data.player.unwrap();
+      assert_eq!(player.translation, [1.5, 2.0, -3.25]);
+      assert_eq!(player.rotation, [0.0, 0.5, 0.0, 0.75]);
+      assert!(data.camera.mode == CameraMode::ThirdPerson);
+      assert_eq!((data.camera.yaw, data.camera.pitch), (45.0, -10.0));
+    } else {
+      assert_eq!(data.play_time, Duration::ZERO);
+      assert!(data.player.is_none());
+      assert!(data.camera.mode == CameraMode::FirstPerson);
+      assert_eq!((data.camera.yaw, data.camera.pitch), (90.0, 0.0));
+    }
+
+    let last_played = if since >= 3 { 1_700_000_000 } else { 0 };
+    assert_eq!(data.last_played, last_played);
+
+    let class = if since >= 4 { Some("warrior") } else { None };
+    assert_eq!(data.class.as_deref(), class);
+
+    match data.vitals {
+      Some(vitals) => {
+        assert!(since >= 5);
+        assert_eq!(
+          [vitals.health, vitals.stamina, vitals.mana],
+          [80.0, 55.5, 20.0]
+        );
+      }
+      None => assert!(since < 5),
+    }
+
+    let progression = &data.progression;
+    let expected = if since >= 6 { (4, 250, 2) } else { (1, 0, 0) };
+    assert_eq!(
+      (
+        progression.level,
+        progression.experience,
+        progression.unspent_points
+      ),
+      expected
+    );
+
+    if since >= 7 {
+      assert_eq!(data.spells.known, ["spark", "ward"]);
+      assert_eq!(data.spells.memorized, ["spark"]);
+    } else {
+      assert!(data.spells.known.is_empty());
+      assert!(data.spells.memorized.is_empty());
+    }
+
+    let inventory = data
+      .inventory
+      .iter()
+      .map(|stack| (stack.id.as_str(), stack.count))
+      .collect::<Vec<_>>();
+    if since >= 8 {
+      assert_eq!(inventory, [("potion", 3), ("sword", 1)]);
+    } else {
+      assert!(inventory.is_empty());
+    }
+
+    assert!(data.equipment.is_empty());
+  }
+
+  #[test]
+  fn migrates_v1() {
+    check(include_bytes!("fixtures/v1.ms"), 1);
+  }
+
+  #[test]
+  fn migrates_v2() {
+    check(include_bytes!("fixtures/v2.ms"), 2);
+  }
+
+  #[test]
+  fn migrates_v3() {
+    check(include_bytes!("fixtures/v3.ms"), 3);
+  }
+
+  #[test]
+  fn migrates_v4() {
+    check(include_bytes!("fixtures/v4.ms"), 4);
+  }
+
+  #[test]
+  fn migrates_v5() {
+    check(include_bytes!("fixtures/v5.ms"), 5);
+  }
+
+  #[test]
+  fn migrates_v6() {
+    check(include_bytes!("fixtures/v6.ms"), 6);
+  }
+
+  #[test]
+  fn migrates_v7() {
+    check(include_bytes!("fixtures/v7.ms"), 7);
+  }
+
+  #[test]
+  fn migrates_v8() {
+    check(include_bytes!("fixtures/v8.ms"), 8);
+  }
+
+  #[test]
+  fn decodes_latest() {
+    let data = SaveDataBuilder::new().name("Aria".to_string()).build();
+    let migrated = migrate(&data.to_bytes().unwrap()).unwrap();
+    assert_eq!(migrated.version, SaveData::LATEST);
+    assert_eq!(migrated.name, "Aria");
+  }
+
+  #[test]
+  fn rejects_unknown_versions() {
+    let mut bytes = include_bytes!("fixtures/v8.ms").to_vec();
+    bytes[..2].copy_from_slice(&(SaveData::LATEST + 1).to_le_bytes());
+    assert!(migrate(&bytes).is_err());
+  }
+
+  #[test]
+  fn rejects_truncated_saves() {
+    let bytes = include_bytes!("fixtures/v8.ms");
+    assert!(migrate(&bytes[..bytes.len() - 1]).is_err());
+    assert!(migrate(&[]).is_err());
+  }
+}
@@ -0,0 +1,141 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+pub const MIN_NAME_LENGTH: usize = 2;
+pub const MAX_NAME_LENGTH: usize = 24;
+
+// device names windows refuses as file names, kept out in case a name
+// ever ends up as one
+const RESERVED_NAMES: &[&str] = &[
+  "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+  "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+  Empty,
+  TooShort,
+  TooLong,
+  InvalidCharacter(char),
+  Reserved,
+  Taken,
+}
+
+impl Display for NameError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      NameError::Empty => write!(f, "Name cannot be empty"),
+      NameError::TooShort => write!(f, "Name must be at least {} characters", MIN_NAME_LENGTH),
+      NameError::TooLong => write!(f, "Name can be at most {} characters", MAX_NAME_LENGTH),
+      NameError::InvalidCharacter(c) => write!(f, "Name cannot contain '{}'", c),
+      NameError::Reserved => write!(f, "That name is reserved"),
+      NameError::Taken => write!(f, "A character with that name already exists"),
+    }
+  }
+}
+
+fn is_allowed(c: char) -> bool {
+  c.is_alphanumeric() || matches!(c, ' ' | '-' | '\'' | '_')
+}
+
+// `taken` holds the names of existing characters, compared case-insensitively
+pub fn validate<'a>(name: &str, taken: impl IntoIterator<Item = &'a str>) -> Result<(), NameError> {
+  if name.trim().is_empty() {
+    return Err(NameError::Empty);
+  }
+
+  if let Some(c) = name.chars().find(|c| !is_allowed(*c)) {
+    return Err(NameError::InvalidCharacter(c));
+  }
+
+  let length = name.chars().count();
+  if length < MIN_NAME_LENGTH {
+    return Err(NameError::TooShort);
+  }
+  if length > MAX_NAME_LENGTH {
+    return Err(NameError::TooLong);
+  }
+
+  let lowercase = name.to_lowercase();
+  if RESERVED_NAMES.contains(&lowercase.as_str()) {
+    return Err(NameError::Reserved);
+  }
+
+  if taken
+    .into_iter()
+    .any(|other| other.to_lowercase() == lowercase)
+  {
+    return Err(NameError::Taken);
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(name: &str) -> Result<(), NameError> {
+    validate(name, [])
+  }
+
+  #[test]
+  fn accepts_names_at_the_length_limits() {
+    assert_eq!(check(&"a".repeat(MIN_NAME_LENGTH)), Ok(()));
+    assert_eq!(check(&"a".repeat(MAX_NAME_LENGTH)), Ok(()));
+  }
+
+  #[test]
+  fn rejects_names_past_the_length_limits() {
+    assert_eq!(check("a"), Err(NameError::TooShort));
+    assert_eq!(
+      check(&"a".repeat(MAX_NAME_LENGTH + 1)),
+      Err(NameError::TooLong)
+    );
+  }
+
+  #[test]
+  fn counts_characters_rather_than_bytes() {
+    assert_eq!(check(&"é".repeat(MAX_NAME_LENGTH)), Ok(()));
+    assert_eq!(check("é"), Err(NameError::TooShort));
+  }
+
+  #[test]
+  fn rejects_empty_names() {
+    assert_eq!(check(""), Err(NameError::Empty));
+    assert_eq!(check("   "), Err(NameError::Empty));
+  }
+
+  #[test]
+  fn rejects_reserved_names_in_any_case() {
+    for name in ["con", "CON", "Nul", "com1", "LPT9", "aUx"] {
+      assert_eq!(check(name), Err(NameError::Reserved), "{}", name);
+    }
+    // only the exact device names
+    assert_eq!(check("console"), Ok(()));
+    assert_eq!(check("com10"), Ok(()));
+  }
+
+  #[test]
+  fn rejects_taken_names_in_any_case() {
+    let taken = ["Aria", "bran"];
+    assert_eq!(validate("aria", taken), Err(NameError::Taken));
+    assert_eq!(validate("BRAN", taken), Err(NameError::Taken));
+    assert_eq!(validate("Cara", taken), Ok(()));
+  }
+
+  #[test]
+  fn accepts_the_allowed_punctuation() {
+    assert_eq!(check("Mary-Kate O'Neil_2"), Ok(()));
+  }
+
+  #[test]
+  fn rejects_other_characters() {
+    for c in [
+      '/', '\\', '.', ':', '*', '?', '"', '<', '>', '|', '\0', '\n',
+    ] {
+      let name = format!("ab{}cd", c);
+      assert_eq!(check(&name), Err(NameError::InvalidCharacter(c)));
+    }
+    assert_eq!(check(".."), Err(NameError::InvalidCharacter('.')));
+  }
+}
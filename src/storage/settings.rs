@@ -1,32 +1,53 @@
 use anyhow::Result;
-use bevy::{prelude::*, window::WindowMode as BevyWindowMode};
+use bevy::{
+  prelude::*,
+  window::{PresentMode as BevyPresentMode, WindowMode as BevyWindowMode},
+};
 use serde::{Deserialize, Serialize};
 use std::{
+  collections::BTreeMap,
   fmt::{Display, Formatter, Result as FmtResult},
   fs,
+  ops::RangeInclusive,
   path::Path,
 };
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use super::writer;
+
 pub mod prelude {
-  pub use super::{Settings, WindowMode};
+  pub use super::{
+    Action, Binding, Device, GraphicsSettings, InputSettings, LookSettings, PresentMode,
+    ResponseCurve, Settings, StickSettings, WindowMode, WindowSettings,
+  };
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Settings {
   pub window: WindowSettings,
+  #[serde(default)]
+  pub saves: SaveSettings,
+  #[serde(default)]
+  pub input: InputSettings,
+  #[serde(default)]
+  pub graphics: GraphicsSettings,
 }
 
 impl Settings {
+  pub const BACKUPS: usize = 1;
+
   pub fn save(&self, file: &Path) -> Result<()> {
     let data = toml::to_string(self)?;
-    fs::write(file, data)?;
-    Ok(())
+    writer::write(file, data.as_bytes(), Self::BACKUPS)
   }
 
   pub fn load(file: &Path) -> Result<Self> {
     let data = fs::read_to_string(file)?;
-    Ok(toml::from_str(&data)?)
+    let mut settings: Self = toml::from_str(&data)?;
+    settings.input.validate();
+    settings.graphics.validate();
+    Ok(settings)
   }
 
   pub fn load_or_default(file: &Path) -> Self {
@@ -44,19 +65,531 @@ impl Default for Settings {
         height: 720,
         width: 1280,
         mode: WindowMode::default(),
+        monitor: None,
+        refresh_rate: None,
       },
+      saves: SaveSettings::default(),
+      input: InputSettings::default(),
+      graphics: GraphicsSettings::default(),
     }
   }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct WindowSettings {
   pub height: u32,
   pub width: u32,
   pub mode: WindowMode,
+  // monitor name as the system reports it, none for the primary
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub monitor: Option<String>,
+  // millihertz, for fullscreen, none for the highest the resolution offers
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub refresh_rate: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveSettings {
+  // seconds between autosaves, zero disables autosaving
+  pub autosave_interval: u32,
+}
+
+impl Default for SaveSettings {
+  fn default() -> Self {
+    Self {
+      autosave_interval: 300,
+    }
+  }
+}
+
+// fields missing from the file keep their defaults
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsSettings {
+  pub present_mode: PresentMode,
+  // frames per second, zero for no cap
+  pub frame_cap: u32,
+  pub msaa_samples: u32,
+  pub shadows: bool,
+  // larger shadow maps, for crisper edges
+  pub high_res_shadows: bool,
+  // size of the 3d image relative to the window
+  pub render_scale: f32,
+}
+
+impl GraphicsSettings {
+  pub const FRAME_CAPS: [u32; 5] = [0, 30, 60, 120, 144];
+  pub const MIN_FRAME_CAP: u32 = 30;
+  pub const MAX_FRAME_CAP: u32 = 360;
+  pub const MSAA_SAMPLES: [u32; 3] = [1, 2, 4];
+  pub const MIN_RENDER_SCALE: f32 = 0.25;
+  pub const MAX_RENDER_SCALE: f32 = 2.0;
+
+  // hand edited files may hold anything, put it back in range rather than failing to start
+  fn validate(&mut self) {
+    let defaults = Self::default();
+
+    if self.frame_cap != 0 && !(Self::MIN_FRAME_CAP..=Self::MAX_FRAME_CAP).contains(&self.frame_cap)
+    {
+      let frame_cap = self
+        .frame_cap
+        .clamp(Self::MIN_FRAME_CAP, Self::MAX_FRAME_CAP);
+      warn!(
+        "frame cap {} out of range, using {}",
+        self.frame_cap, frame_cap
+      );
+      self.frame_cap = frame_cap;
+    }
+
+    if !Self::MSAA_SAMPLES.contains(&self.msaa_samples) {
+      warn!(
+        "unsupported msaa sample count {}, using {}",
+        self.msaa_samples, defaults.msaa_samples
+      );
+      self.msaa_samples = defaults.msaa_samples;
+    }
+
+    if !(Self::MIN_RENDER_SCALE..=Self::MAX_RENDER_SCALE).contains(&self.render_scale) {
+      // nan fails the range check as well, and clamping it would keep it nan
+      let render_scale = if self.render_scale.is_nan() {
+        defaults.render_scale
+      } else {
+        self
+          .render_scale
+          .clamp(Self::MIN_RENDER_SCALE, Self::MAX_RENDER_SCALE)
+      };
+      warn!(
+        "render scale {} out of range, using {}",
+        self.render_scale, render_scale
+      );
+      self.render_scale = render_scale;
+    }
+  }
+
+  pub fn msaa(&self) -> Msaa {
+    match self.msaa_samples {
+      1 => Msaa::Off,
+      2 => Msaa::Sample2,
+      _ => Msaa::Sample4,
+    }
+  }
+}
+
+impl Default for GraphicsSettings {
+  fn default() -> Self {
+    Self {
+      present_mode: PresentMode::default(),
+      frame_cap: 0,
+      msaa_samples: 4,
+      shadows: true,
+      high_res_shadows: false,
+      render_scale: 1.0,
+    }
+  }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+  #[default]
+  Vsync,
+  // tears, but shows frames as soon as they're done
+  Immediate,
+}
+
+impl Display for PresentMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      PresentMode::Vsync => write!(f, "VSync"),
+      PresentMode::Immediate => write!(f, "Immediate"),
+    }
+  }
+}
+
+impl From<PresentMode> for BevyPresentMode {
+  // the auto modes fall back to whatever the platform supports instead of panicking
+  fn from(value: PresentMode) -> Self {
+    match value {
+      PresentMode::Vsync => Self::AutoVsync,
+      PresentMode::Immediate => Self::AutoNoVsync,
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+  MoveForward,
+  MoveBack,
+  MoveLeft,
+  MoveRight,
+  Sprint,
+  ToggleCamera,
+  Attack,
+  Interact,
+  Spell1,
+  Spell2,
+  Spell3,
+  Spell4,
+  Inventory,
+  LevelUp,
+  QuickSave,
+  Pause,
+  MenuUp,
+  MenuDown,
+  MenuConfirm,
+  MenuBack,
+  UiPlayground,
+}
+
+// actions only clash with the ones that can be pressed at the same time
+#[derive(PartialEq, Eq)]
+enum ActionContext {
+  Gameplay,
+  Menu,
+  Global,
+}
+
+impl Action {
+  fn context(self) -> ActionContext {
+    match self {
+      Action::Pause | Action::UiPlayground => ActionContext::Global,
+      Action::MenuUp | Action::MenuDown | Action::MenuConfirm | Action::MenuBack => {
+        ActionContext::Menu
+      }
+      _ => ActionContext::Gameplay,
+    }
+  }
+
+  pub fn conflicts_with(self, other: Action) -> bool {
+    let (a, b) = (self.context(), other.context());
+    a == b || a == ActionContext::Global || b == ActionContext::Global
+  }
+
+  pub fn default_bindings(self) -> ActionBindings {
+    use GamepadButtonType as Pad;
+    let (keyboard, gamepad) = match self {
+      Action::MoveForward => (Some(Binding::Key(KeyCode::W)), None),
+      Action::MoveBack => (Some(Binding::Key(KeyCode::S)), None),
+      Action::MoveLeft => (Some(Binding::Key(KeyCode::A)), None),
+      Action::MoveRight => (Some(Binding::Key(KeyCode::D)), None),
+      Action::Sprint => (Some(Binding::Key(KeyCode::ShiftLeft)), Some(Pad::LeftThumb)),
+      Action::ToggleCamera => (Some(Binding::Key(KeyCode::F3)), Some(Pad::Select)),
+      Action::Attack => (
+        Some(Binding::Mouse(MouseButton::Left)),
+        Some(Pad::RightTrigger2),
+      ),
+      Action::Interact => (Some(Binding::Key(KeyCode::E)), Some(Pad::South)),
+      Action::Spell1 => (Some(Binding::Key(KeyCode::Key1)), Some(Pad::DPadUp)),
+      Action::Spell2 => (Some(Binding::Key(KeyCode::Key2)), Some(Pad::DPadRight)),
+      Action::Spell3 => (Some(Binding::Key(KeyCode::Key3)), Some(Pad::DPadDown)),
+      Action::Spell4 => (Some(Binding::Key(KeyCode::Key4)), Some(Pad::DPadLeft)),
+      Action::Inventory => (Some(Binding::Key(KeyCode::I)), Some(Pad::North)),
+      Action::LevelUp => (Some(Binding::Key(KeyCode::L)), None),
+      Action::QuickSave => (Some(Binding::Key(KeyCode::F5)), None),
+      Action::Pause => (Some(Binding::Key(KeyCode::Escape)), Some(Pad::Start)),
+      Action::MenuUp => (Some(Binding::Key(KeyCode::Up)), Some(Pad::DPadUp)),
+      Action::MenuDown => (Some(Binding::Key(KeyCode::Down)), Some(Pad::DPadDown)),
+      Action::MenuConfirm => (Some(Binding::Key(KeyCode::Return)), Some(Pad::South)),
+      Action::MenuBack => (Some(Binding::Key(KeyCode::Back)), Some(Pad::East)),
+      Action::UiPlayground => (Some(Binding::Key(KeyCode::F9)), None),
+    };
+    ActionBindings {
+      keyboard,
+      gamepad: gamepad.map(Binding::Gamepad),
+    }
+  }
+}
+
+impl Display for Action {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Action::MoveForward => write!(f, "Move Forward"),
+      Action::MoveBack => write!(f, "Move Back"),
+      Action::MoveLeft => write!(f, "Move Left"),
+      Action::MoveRight => write!(f, "Move Right"),
+      Action::Sprint => write!(f, "Sprint"),
+      Action::ToggleCamera => write!(f, "Toggle Camera"),
+      Action::Attack => write!(f, "Attack"),
+      Action::Interact => write!(f, "Interact"),
+      Action::Spell1 => write!(f, "Spell 1"),
+      Action::Spell2 => write!(f, "Spell 2"),
+      Action::Spell3 => write!(f, "Spell 3"),
+      Action::Spell4 => write!(f, "Spell 4"),
+      Action::Inventory => write!(f, "Inventory"),
+      Action::LevelUp => write!(f, "Level Up"),
+      Action::QuickSave => write!(f, "Quick Save"),
+      Action::Pause => write!(f, "Pause"),
+      Action::MenuUp => write!(f, "Menu Up"),
+      Action::MenuDown => write!(f, "Menu Down"),
+      Action::MenuConfirm => write!(f, "Menu Confirm"),
+      Action::MenuBack => write!(f, "Menu Back"),
+      Action::UiPlayground => write!(f, "UI Playground"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+  Key(KeyCode),
+  Mouse(MouseButton),
+  Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+  pub fn device(self) -> Device {
+    match self {
+      Binding::Key(_) | Binding::Mouse(_) => Device::Keyboard,
+      Binding::Gamepad(_) => Device::Gamepad,
+    }
+  }
+}
+
+impl Display for Binding {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Binding::Key(key) => write!(f, "{:?}", key),
+      Binding::Mouse(MouseButton::Other(button)) => write!(f, "Mouse {}", button),
+      Binding::Mouse(button) => write!(f, "Mouse {:?}", button),
+      Binding::Gamepad(button) => write!(f, "{:?}", button),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Device {
+  // keyboard and mouse
+  Keyboard,
+  Gamepad,
+}
+
+impl Display for Device {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Device::Keyboard => write!(f, "Keyboard & Mouse"),
+      Device::Gamepad => write!(f, "Gamepad"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActionBindings {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub keyboard: Option<Binding>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub gamepad: Option<Binding>,
+}
+
+impl ActionBindings {
+  fn get_mut(&mut self, device: Device) -> &mut Option<Binding> {
+    match device {
+      Device::Keyboard => &mut self.keyboard,
+      Device::Gamepad => &mut self.gamepad,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputSettings {
+  // actions missing from the file keep their default bindings
+  #[serde(default)]
+  bindings: BTreeMap<Action, ActionBindings>,
+  #[serde(default = "LookSettings::mouse")]
+  pub mouse_look: LookSettings,
+  #[serde(default = "LookSettings::gamepad")]
+  pub gamepad_look: LookSettings,
+  #[serde(default)]
+  pub sticks: StickSettings,
+}
+
+impl InputSettings {
+  pub fn bindings(&self, action: Action) -> ActionBindings {
+    self
+      .bindings
+      .get(&action)
+      .copied()
+      .unwrap_or_else(|| action.default_bindings())
+  }
+
+  pub fn binding(&self, action: Action, device: Device) -> Option<Binding> {
+    let bindings = self.bindings(action);
+    match device {
+      Device::Keyboard => bindings.keyboard,
+      Device::Gamepad => bindings.gamepad,
+    }
+  }
+
+  // `binding` replaces whatever `action` had on the binding's device
+  pub fn bind(&mut self, action: Action, device: Device, binding: Option<Binding>) {
+    *self
+      .bindings
+      .entry(action)
+      .or_insert_with(|| action.default_bindings())
+      .get_mut(device) = binding;
+  }
+
+  // hand edited values are put back in range like GraphicsSettings::validate does
+  fn validate(&mut self) {
+    self.mouse_look.validate(
+      "mouse",
+      LookSettings::MOUSE_SENSITIVITY,
+      LookSettings::mouse(),
+    );
+    self.gamepad_look.validate(
+      "gamepad",
+      LookSettings::GAMEPAD_SENSITIVITY,
+      LookSettings::gamepad(),
+    );
+    self.sticks.validate();
+  }
+
+  // another action that `binding` would clash with if given to `action`
+  pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+    Action::iter().find(|&other| {
+      other != action
+        && action.conflicts_with(other)
+        && self.binding(other, binding.device()) == Some(binding)
+    })
+  }
+}
+
+impl Default for InputSettings {
+  fn default() -> Self {
+    Self {
+      bindings: Action::iter()
+        .map(|action| (action, action.default_bindings()))
+        .collect(),
+      mouse_look: LookSettings::mouse(),
+      gamepad_look: LookSettings::gamepad(),
+      sticks: StickSettings::default(),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LookSettings {
+  // degrees per pixel for the mouse, per frame at full tilt for a stick
+  pub sensitivity: f32,
+  pub invert_x: bool,
+  pub invert_y: bool,
+}
+
+impl LookSettings {
+  pub const MOUSE_SENSITIVITY: RangeInclusive<f32> = 0.01..=1.0;
+  pub const GAMEPAD_SENSITIVITY: RangeInclusive<f32> = 0.1..=5.0;
+
+  fn mouse() -> Self {
+    Self {
+      sensitivity: 0.1,
+      invert_x: false,
+      invert_y: false,
+    }
+  }
+
+  fn gamepad() -> Self {
+    Self {
+      sensitivity: 1.0,
+      invert_x: false,
+      invert_y: false,
+    }
+  }
+
+  fn validate(&mut self, name: &str, range: RangeInclusive<f32>, defaults: Self) {
+    let sensitivity = clamp_or(self.sensitivity, &range, defaults.sensitivity);
+    if sensitivity != self.sensitivity {
+      warn!(
+        "{} sensitivity {} out of range, using {}",
+        name, self.sensitivity, sensitivity
+      );
+      self.sensitivity = sensitivity;
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StickSettings {
+  // tilt below the inner deadzone is ignored, above the outer it counts as full
+  pub inner_deadzone: f32,
+  pub outer_deadzone: f32,
+  pub curve: ResponseCurve,
+}
+
+impl StickSettings {
+  pub const INNER_DEADZONE: RangeInclusive<f32> = 0.0..=0.5;
+  pub const OUTER_DEADZONE: RangeInclusive<f32> = 0.5..=1.0;
+
+  // the ranges meet in the middle, so inner never passes outer
+  fn validate(&mut self) {
+    let defaults = Self::default();
+
+    let inner_deadzone = clamp_or(
+      self.inner_deadzone,
+      &Self::INNER_DEADZONE,
+      defaults.inner_deadzone,
+    );
+    if inner_deadzone != self.inner_deadzone {
+      warn!(
+        "inner deadzone {} out of range, using {}",
+        self.inner_deadzone, inner_deadzone
+      );
+      self.inner_deadzone = inner_deadzone;
+    }
+
+    let outer_deadzone = clamp_or(
+      self.outer_deadzone,
+      &Self::OUTER_DEADZONE,
+      defaults.outer_deadzone,
+    );
+    if outer_deadzone != self.outer_deadzone {
+      warn!(
+        "outer deadzone {} out of range, using {}",
+        self.outer_deadzone, outer_deadzone
+      );
+      self.outer_deadzone = outer_deadzone;
+    }
+  }
+}
+
+impl Default for StickSettings {
+  fn default() -> Self {
+    Self {
+      inner_deadzone: 0.15,
+      outer_deadzone: 0.95,
+      curve: ResponseCurve::default(),
+    }
+  }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseCurve {
+  #[default]
+  Linear,
+  // finer control near the center
+  Quadratic,
 }
 
-#[derive(Default, Clone, Copy, Serialize, Deserialize, EnumIter)]
+impl ResponseCurve {
+  // `tilt` in 0..=1
+  pub fn apply(self, tilt: f32) -> f32 {
+    match self {
+      ResponseCurve::Linear => tilt,
+      ResponseCurve::Quadratic => tilt * tilt,
+    }
+  }
+}
+
+impl Display for ResponseCurve {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      ResponseCurve::Linear => write!(f, "Linear"),
+      ResponseCurve::Quadratic => write!(f, "Quadratic"),
+    }
+  }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
 pub enum WindowMode {
   #[default]
   Windowed,
@@ -91,12 +624,125 @@ impl From<BevyWindowMode> for WindowMode {
   }
 }
 
-impl Into<BevyWindowMode> for WindowMode {
-  fn into(self) -> BevyWindowMode {
-    match self {
-      Self::Windowed => BevyWindowMode::Windowed,
-      Self::Fullscreen => BevyWindowMode::Fullscreen,
-      Self::Borderless => BevyWindowMode::BorderlessFullscreen,
+impl From<WindowMode> for BevyWindowMode {
+  fn from(value: WindowMode) -> Self {
+    match value {
+      WindowMode::Windowed => Self::Windowed,
+      WindowMode::Fullscreen => Self::Fullscreen,
+      WindowMode::Borderless => Self::BorderlessFullscreen,
     }
   }
 }
+
+// nan would pass through a clamp untouched, so it takes the default instead
+fn clamp_or(value: f32, range: &RangeInclusive<f32>, default: f32) -> f32 {
+  if value.is_nan() {
+    default
+  } else {
+    value.clamp(*range.start(), *range.end())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // loads the default settings with `edit` made to their input table
+  fn load(edit: impl FnOnce(&mut toml::Table)) -> Settings {
+    let mut table: toml::Table =
+      toml::from_str(&toml::to_string(&Settings::default()).unwrap()).unwrap();
+    edit(table["input"].as_table_mut().unwrap());
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("settings.toml");
+    fs::write(&file, toml::to_string(&table).unwrap()).unwrap();
+    Settings::load(&file).unwrap()
+  }
+
+  fn set(input: &mut toml::Table, section: &str, key: &str, value: f64) {
+    input[section]
+      .as_table_mut()
+      .unwrap()
+      .insert(key.to_string(), value.into());
+  }
+
+  #[test]
+  fn missing_bindings_fall_back_to_defaults() {
+    let settings = load(|input| {
+      input.remove("bindings");
+    });
+    for action in Action::iter() {
+      assert!(settings.input.bindings(action) == action.default_bindings());
+    }
+  }
+
+  #[test]
+  fn in_range_input_settings_load_unchanged() {
+    let settings = load(|input| {
+      set(input, "sticks", "inner_deadzone", 0.25);
+      set(input, "sticks", "outer_deadzone", 0.75);
+      set(input, "mouse_look", "sensitivity", 0.5);
+    });
+    assert_eq!(settings.input.sticks.inner_deadzone, 0.25);
+    assert_eq!(settings.input.sticks.outer_deadzone, 0.75);
+    assert_eq!(settings.input.mouse_look.sensitivity, 0.5);
+  }
+
+  #[test]
+  fn out_of_range_deadzones_are_clamped() {
+    let settings = load(|input| {
+      set(input, "sticks", "inner_deadzone", 1.0);
+      set(input, "sticks", "outer_deadzone", 3.0);
+    });
+    assert_eq!(settings.input.sticks.inner_deadzone, 0.5);
+    assert_eq!(settings.input.sticks.outer_deadzone, 1.0);
+
+    let settings = load(|input| {
+      set(input, "sticks", "inner_deadzone", -0.5);
+      set(input, "sticks", "outer_deadzone", 0.1);
+    });
+    assert_eq!(settings.input.sticks.inner_deadzone, 0.0);
+    assert_eq!(settings.input.sticks.outer_deadzone, 0.5);
+  }
+
+  #[test]
+  fn nan_deadzones_reset_to_defaults() {
+    let settings = load(|input| {
+      set(input, "sticks", "inner_deadzone", f64::NAN);
+      set(input, "sticks", "outer_deadzone", f64::NAN);
+    });
+    let defaults = StickSettings::default();
+    assert_eq!(
+      settings.input.sticks.inner_deadzone,
+      defaults.inner_deadzone
+    );
+    assert_eq!(
+      settings.input.sticks.outer_deadzone,
+      defaults.outer_deadzone
+    );
+  }
+
+  #[test]
+  fn bad_sensitivities_are_clamped_or_reset() {
+    let settings = load(|input| {
+      set(input, "mouse_look", "sensitivity", f64::NAN);
+      set(input, "gamepad_look", "sensitivity", -2.0);
+    });
+    assert_eq!(
+      settings.input.mouse_look.sensitivity,
+      LookSettings::mouse().sensitivity
+    );
+    assert_eq!(
+      settings.input.gamepad_look.sensitivity,
+      *LookSettings::GAMEPAD_SENSITIVITY.start()
+    );
+
+    let settings = load(|input| {
+      set(input, "mouse_look", "sensitivity", f64::INFINITY);
+    });
+    assert_eq!(
+      settings.input.mouse_look.sensitivity,
+      *LookSettings::MOUSE_SENSITIVITY.end()
+    );
+  }
+}
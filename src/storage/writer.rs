@@ -0,0 +1,216 @@
+use anyhow::Result;
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  io::Write,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex, PoisonError,
+  },
+};
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// one lock per path written, so a blocking save never rotates backups under a background one
+static PATH_LOCKS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+// writes `data` to `path` without ever leaving a truncated file in its
+// place, keeping up to `backups` previous versions as `path.1`, `path.2`, ...
+pub fn write(path: &Path, data: &[u8], backups: usize) -> Result<()> {
+  let lock = path_lock(path);
+  let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+  if let Some(dir) = parent_dir(path) {
+    fs::create_dir_all(dir)?;
+  }
+
+  // unique per write so concurrent saves of the same file never share a temp file
+  let tmp_path = suffixed(
+    path,
+    &format!(
+      "{}.{}.tmp",
+      std::process::id(),
+      TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ),
+  );
+  {
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+  }
+
+  if backups > 0 && path.exists() {
+    rotate(path, backups)?;
+  }
+
+  fs::rename(&tmp_path, path)?;
+  sync_dir(path)
+}
+
+pub fn backup_path(path: &Path, index: usize) -> PathBuf {
+  suffixed(path, &index.to_string())
+}
+
+// existing backups of `path` among the `count` kept, newest first
+pub fn backups(path: &Path, count: usize) -> impl Iterator<Item = PathBuf> + '_ {
+  // a backup lost along the way leaves a gap, the ones after it are still worth offering
+  (1..=count)
+    .map(|index| backup_path(path, index))
+    .filter(|backup| backup.exists())
+}
+
+fn path_lock(path: &Path) -> Arc<Mutex<()>> {
+  PATH_LOCKS
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .entry(path.to_path_buf())
+    .or_default()
+    .clone()
+}
+
+fn rotate(path: &Path, backups: usize) -> Result<()> {
+  for index in (1..backups).rev() {
+    let from = backup_path(path, index);
+    if from.exists() {
+      fs::rename(&from, backup_path(path, index + 1))?;
+    }
+  }
+
+  // copy rather than move so the primary file exists at every point in time
+  let newest = backup_path(path, 1);
+  fs::copy(path, &newest)?;
+  File::open(&newest)?.sync_all()?;
+  Ok(())
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+  let mut path = path.as_os_str().to_owned();
+  path.push(".");
+  path.push(suffix);
+  PathBuf::from(path)
+}
+
+fn parent_dir(path: &Path) -> Option<&Path> {
+  path.parent().filter(|dir| !dir.as_os_str().is_empty())
+}
+
+// persists the rename itself, not just the file contents
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<()> {
+  if let Some(dir) = parent_dir(path) {
+    File::open(dir)?.sync_all()?;
+  }
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<()> {
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread;
+  use tempfile::TempDir;
+
+  fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+  }
+
+  #[test]
+  fn rotates_backups_newest_first() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("save.ms");
+    for version in 1..=5 {
+      write(&path, format!("v{}", version).as_bytes(), 3).unwrap();
+    }
+
+    assert_eq!(read(&path), "v5");
+    let backups = backups(&path, 3)
+      .map(|backup| read(&backup))
+      .collect::<Vec<_>>();
+    assert_eq!(backups, ["v4", "v3", "v2"]);
+    assert!(!backup_path(&path, 4).exists());
+  }
+
+  #[test]
+  fn keeps_no_backups_when_asked_for_none() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("settings.toml");
+    write(&path, b"a", 0).unwrap();
+    write(&path, b"b", 0).unwrap();
+
+    assert_eq!(read(&path), "b");
+    assert!(!backup_path(&path, 1).exists());
+  }
+
+  #[test]
+  fn offers_backups_past_a_gap() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("save.ms");
+    fs::write(backup_path(&path, 2), "older").unwrap();
+    fs::write(backup_path(&path, 3), "oldest").unwrap();
+
+    let found = backups(&path, 3).collect::<Vec<_>>();
+    assert_eq!(found, [backup_path(&path, 2), backup_path(&path, 3)]);
+    // only the kept backups count
+    assert_eq!(backups(&path, 2).count(), 1);
+  }
+
+  #[test]
+  fn rotates_past_a_gap() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("save.ms");
+    fs::write(&path, "current").unwrap();
+    fs::write(backup_path(&path, 2), "older").unwrap();
+
+    write(&path, b"new", 3).unwrap();
+
+    assert_eq!(read(&path), "new");
+    assert_eq!(read(&backup_path(&path, 1)), "current");
+    assert_eq!(read(&backup_path(&path, 3)), "older");
+  }
+
+  #[test]
+  fn survives_a_crash_mid_write() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("save.ms");
+    write(&path, b"good", 3).unwrap();
+
+    // a write that died before its rename leaves only its temp file behind
+    let tmp = suffixed(&path, "999.0.tmp");
+    fs::write(&tmp, b"trunc").unwrap();
+
+    assert_eq!(read(&path), "good");
+    assert_eq!(backups(&path, 3).count(), 0);
+
+    write(&path, b"next", 3).unwrap();
+    assert_eq!(read(&path), "next");
+    assert_eq!(read(&backup_path(&path, 1)), "good");
+  }
+
+  #[test]
+  fn concurrent_writes_keep_every_backup() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("save.ms");
+    let writers = (0..4)
+      .map(|writer| {
+        let path = path.clone();
+        thread::spawn(move || write(&path, format!("w{}", writer).as_bytes(), 3).unwrap())
+      })
+      .collect::<Vec<_>>();
+    for writer in writers {
+      writer.join().unwrap();
+    }
+
+    // each write rotated what the one before it left, so all four are still around
+    let mut all = backups(&path, 3)
+      .chain([path.clone()])
+      .map(|file| read(&file))
+      .collect::<Vec<_>>();
+    all.sort();
+    assert_eq!(all, ["w0", "w1", "w2", "w3"]);
+  }
+}